@@ -0,0 +1,564 @@
+use crate::game::{Direction, Game, ARENA_CELLS, ARENA_SIZE};
+use crate::rng::Rng;
+use crate::weights::TUNED_WEIGHTS;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Personality {
+    Standard,
+    Aggressor,
+    Defender,
+    Chaotic,
+    Tuned,
+}
+
+#[derive(Clone, Copy)]
+pub enum Strategy {
+    // Only looks straight ahead, left and right (the original AI)
+    Reflex,
+    // Flood fills behind each candidate move and picks the most space
+    Space,
+    // Heads for where the nearest opponent will be `lead` ticks from now
+    Cutter { lead: usize },
+    // Keeps as many walls alongside as possible to fill space tightly
+    Hugger,
+    // Swerves at random, otherwise drives like Reflex
+    Chaos { turn_chance: u8 },
+    // Weighted sum of board features, weights tuned by self-play
+    Eval { weights: Weights },
+}
+
+pub const FEATURES: usize = 7;
+pub type Weights = [i32; FEATURES];
+pub const FEATURE_NAMES: [&str; FEATURES] = [
+    "space",     // cells reachable after the move
+    "territory", // cells we'd reach before any opponent
+    "opponent",  // distance to the nearest opponent
+    "contact",   // walls and trails next to the new cell
+    "ahead",     // clear line ahead after the move
+    "sides",     // shorter of the clear lines to the left and right
+    "contested", // 1 if an opponent could move into the same cell
+];
+
+// Cutter won't chase into anywhere with less room than this
+const CUTTER_MIN_ROOM: usize = 100;
+// Least flood fill budget Aggressor and Defender check room with, so they
+// still see dead ends on difficulties that don't search
+const PERSONALITY_MIN_BUDGET: usize = 600;
+// The tuned weights were found with Insane's whole-board fills, so Eval
+// always scores with those
+const EVAL_BUDGET: usize = ARENA_CELLS;
+
+#[derive(Clone, Copy)]
+pub struct AiParams {
+    pub strategy: Strategy,
+    pub reaction_delay: u8,   // ticks between noticing a wall and turning
+    pub lookahead: usize,     // react once a wall is this close
+    pub mistake_chance: u8,   // percent chance of a random move when reacting
+    pub search_budget: usize, // max cells flood filled per candidate move
+}
+
+impl AiParams {
+    /// Swaps the difficulty's default strategy for the personality's one,
+    /// keeping the difficulty's reaction and search knobs. Aggressor and
+    /// Defender search at least PERSONALITY_MIN_BUDGET cells whatever the
+    /// difficulty.
+    pub const fn with_personality(mut self, personality: Personality) -> Self {
+        if let Some(strategy) = personality.strategy() {
+            self.strategy = strategy;
+            let checks_room = matches!(strategy, Strategy::Cutter { .. } | Strategy::Hugger);
+            if checks_room && self.search_budget < PERSONALITY_MIN_BUDGET {
+                self.search_budget = PERSONALITY_MIN_BUDGET;
+            }
+        }
+        self
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(self) -> &'static str {
+        use Difficulty::*;
+        match self {
+            Easy => "Easy",
+            Normal => "Normal",
+            Hard => "Hard",
+            Insane => "Insane",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        use Difficulty::*;
+        match self {
+            Easy => Normal,
+            Normal => Hard,
+            Hard => Insane,
+            Insane => Easy,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        use Difficulty::*;
+        match self {
+            Easy => Insane,
+            Normal => Easy,
+            Hard => Normal,
+            Insane => Hard,
+        }
+    }
+
+    pub const fn params(self) -> AiParams {
+        use Difficulty::*;
+        match self {
+            Easy => AiParams {
+                strategy: Strategy::Reflex,
+                reaction_delay: 2,
+                lookahead: 3,
+                mistake_chance: 10,
+                search_budget: 0,
+            },
+            Normal => AiParams {
+                strategy: Strategy::Reflex,
+                reaction_delay: 0,
+                lookahead: 3,
+                mistake_chance: 3,
+                search_budget: 0,
+            },
+            Hard => AiParams {
+                strategy: Strategy::Space,
+                reaction_delay: 0,
+                lookahead: ARENA_SIZE,
+                mistake_chance: 2,
+                search_budget: 600,
+            },
+            Insane => AiParams {
+                strategy: Strategy::Space,
+                reaction_delay: 0,
+                lookahead: ARENA_SIZE,
+                mistake_chance: 0,
+                search_budget: ARENA_CELLS,
+            },
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(b: u8) -> Option<Self> {
+        use Difficulty::*;
+        match b {
+            0 => Some(Easy),
+            1 => Some(Normal),
+            2 => Some(Hard),
+            3 => Some(Insane),
+            _ => None,
+        }
+    }
+}
+
+impl Personality {
+    pub const ALL: [Personality; 5] = [
+        Personality::Standard,
+        Personality::Aggressor,
+        Personality::Defender,
+        Personality::Chaotic,
+        Personality::Tuned,
+    ];
+
+    pub fn name(self) -> &'static str {
+        use Personality::*;
+        match self {
+            Standard => "Standard",
+            Aggressor => "Aggressor",
+            Defender => "Defender",
+            Chaotic => "Chaotic",
+            Tuned => "Tuned",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        use Personality::*;
+        match self {
+            Standard => Aggressor,
+            Aggressor => Defender,
+            Defender => Chaotic,
+            Chaotic => Tuned,
+            Tuned => Standard,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        use Personality::*;
+        match self {
+            Standard => Tuned,
+            Aggressor => Standard,
+            Defender => Aggressor,
+            Chaotic => Defender,
+            Tuned => Chaotic,
+        }
+    }
+
+    /// None keeps whatever the difficulty uses
+    pub const fn strategy(self) -> Option<Strategy> {
+        use Personality::*;
+        match self {
+            Standard => None,
+            Aggressor => Some(Strategy::Cutter { lead: 6 }),
+            Defender => Some(Strategy::Hugger),
+            Chaotic => Some(Strategy::Chaos { turn_chance: 15 }),
+            Tuned => Some(Strategy::Eval {
+                weights: TUNED_WEIGHTS,
+            }),
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(b: u8) -> Option<Self> {
+        use Personality::*;
+        match b {
+            0 => Some(Standard),
+            1 => Some(Aggressor),
+            2 => Some(Defender),
+            3 => Some(Chaotic),
+            4 => Some(Tuned),
+            _ => None,
+        }
+    }
+}
+
+pub struct Ai {
+    params: AiParams,
+    rng: Rng,
+    reaction: u8,
+    pub trace: Trace,
+}
+
+/// What the AI weighed up on its last think. Only recorded with the
+/// `ai-debug` feature, otherwise it's empty and compiles away.
+#[derive(Default)]
+pub struct Trace {
+    #[cfg(feature = "ai-debug")]
+    pub reason: &'static str,
+    #[cfg(feature = "ai-debug")]
+    pub scores: Vec<(Direction, String)>,
+    #[cfg(feature = "ai-debug")]
+    pub chosen: Option<Direction>,
+}
+
+impl Trace {
+    pub const fn new() -> Self {
+        Trace {
+            #[cfg(feature = "ai-debug")]
+            reason: "",
+            #[cfg(feature = "ai-debug")]
+            scores: Vec::new(),
+            #[cfg(feature = "ai-debug")]
+            chosen: None,
+        }
+    }
+
+    #[allow(unused_variables)]
+    fn score<S: core::fmt::Debug>(&mut self, dir: Direction, score: &S) {
+        #[cfg(feature = "ai-debug")]
+        self.scores.push((dir, format!("{:?}", score)));
+    }
+
+    #[allow(unused_variables)]
+    fn decide(&mut self, reason: &'static str, dir: Direction) -> Direction {
+        #[cfg(feature = "ai-debug")]
+        {
+            self.reason = reason;
+            self.chosen = Some(dir);
+        }
+        dir
+    }
+}
+
+impl Ai {
+    pub const fn new(params: AiParams, seed: u32) -> Self {
+        Ai {
+            params,
+            rng: Rng::new(seed),
+            reaction: 0,
+            trace: Trace::new(),
+        }
+    }
+
+    /// Called once per tick, returns the direction for the given player to move in
+    pub fn think(&mut self, game: &Game, player: usize) -> Direction {
+        #[cfg(feature = "ai-debug")]
+        self.trace.scores.clear();
+        let pos = game.players[player].position;
+        let dir = game.players[player].direction;
+        let danger = game.next_ahead(pos, dir) <= self.params.lookahead;
+        // These steer all the time, not just when something is in the way
+        let proactive = matches!(
+            self.params.strategy,
+            Strategy::Cutter { .. } | Strategy::Chaos { .. }
+        );
+        if !danger && !proactive {
+            self.reaction = 0;
+            return self.trace.decide("clear", dir);
+        }
+        // Seen the wall, but haven't reacted yet
+        if self.reaction < self.params.reaction_delay {
+            self.reaction += 1;
+            return self.trace.decide("reacting", dir);
+        }
+        self.reaction = 0;
+
+        // Misjudge which way is best, but don't steer straight into a wall
+        if self.rng.chance(self.params.mistake_chance) {
+            if let Some(d) = self.random_move(game, pos, dir) {
+                return self.trace.decide("mistake", d);
+            }
+        }
+
+        let budget = self.params.search_budget;
+        let trace = &mut self.trace;
+        let (reason, d) = match self.params.strategy {
+            Strategy::Reflex => ("reflex", reflex(game, pos, dir, trace)),
+            Strategy::Space => ("space", space(game, player, budget, trace)),
+            Strategy::Cutter { lead } => ("cutter", cutter(game, player, lead, budget, trace)),
+            Strategy::Hugger => ("hugger", hugger(game, player, budget, trace)),
+            Strategy::Eval { weights } => ("eval", eval(game, player, &weights, trace)),
+            Strategy::Chaos { turn_chance } => {
+                if self.rng.chance(turn_chance) {
+                    ("swerve", self.random_move(game, pos, dir).unwrap_or(dir))
+                } else if danger {
+                    ("reflex", reflex(game, pos, dir, trace))
+                } else {
+                    ("clear", dir)
+                }
+            }
+        };
+        self.trace.decide(reason, d)
+    }
+
+    /// Any move that doesn't crash straight away
+    fn random_move(
+        &mut self,
+        game: &Game,
+        pos: (usize, usize),
+        dir: Direction,
+    ) -> Option<Direction> {
+        let mut options = [dir; 3];
+        let mut count = 0;
+        for candidate in [dir, dir.left_turn(), dir.right_turn()] {
+            if game.is_free(candidate.next_position(pos)) {
+                options[count] = candidate;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        Some(options[self.rng.below(count as u32) as usize])
+    }
+}
+
+fn reflex(game: &Game, pos: (usize, usize), dir: Direction, trace: &mut Trace) -> Direction {
+    let na = game.next_ahead(pos, dir);
+    let nl = game.next_left(pos, dir);
+    let nr = game.next_right(pos, dir);
+    trace.score(dir, &na);
+    trace.score(dir.left_turn(), &nl);
+    trace.score(dir.right_turn(), &nr);
+    if nl >= nr && nl > na {
+        dir.left_turn()
+    } else if nr >= nl && nr > na {
+        dir.right_turn()
+    } else {
+        dir
+    }
+}
+
+/// Picks the free move with the highest score, checking straight first so
+/// ties keep going straight
+fn best_move<S, F>(game: &Game, player: usize, trace: &mut Trace, mut score: F) -> Direction
+where
+    S: PartialOrd + core::fmt::Debug,
+    F: FnMut((usize, usize), Direction) -> S,
+{
+    let pos = game.players[player].position;
+    let dir = game.players[player].direction;
+    let mut best = dir;
+    let mut best_score = None;
+    for candidate in [dir, dir.left_turn(), dir.right_turn()] {
+        let next = candidate.next_position(pos);
+        if !game.is_free(next) {
+            continue;
+        }
+        let s = score(next, candidate);
+        trace.score(candidate, &s);
+        if best_score.as_ref().is_none_or(|b| s > *b) {
+            best = candidate;
+            best_score = Some(s);
+        }
+    }
+    best
+}
+
+/// Whether an opponent could also move into this cell, risking a head-on crash
+fn contested(game: &Game, player: usize, cell: (usize, usize)) -> bool {
+    game.players
+        .iter()
+        .enumerate()
+        .any(|(i, p)| i != player && p.alive && manhattan(p.position, cell) == 1)
+}
+
+/// How many of a cell's neighbours are walls or trails
+fn contact(game: &Game, (r, c): (usize, usize)) -> usize {
+    [(r - 1, c), (r + 1, c), (r, c - 1), (r, c + 1)]
+        .into_iter()
+        .filter(|&cell| !game.is_free(cell))
+        .count()
+}
+
+fn manhattan(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+fn space(game: &Game, player: usize, budget: usize, trace: &mut Trace) -> Direction {
+    let pos = game.players[player].position;
+    best_move(game, player, trace, |next, candidate| {
+        // Avoid head-ons, then the most room, then the longest clear line
+        (
+            !contested(game, player, next),
+            reachable_area(game, pos, next, budget),
+            game.next_ahead(pos, candidate),
+        )
+    })
+}
+
+fn cutter(game: &Game, player: usize, lead: usize, budget: usize, trace: &mut Trace) -> Direction {
+    let pos = game.players[player].position;
+    // Where the nearest opponent ends up if it keeps going straight
+    let target = game
+        .players
+        .iter()
+        .enumerate()
+        .filter(|&(i, p)| i != player && p.alive)
+        .min_by_key(|(_, p)| manhattan(p.position, pos))
+        .map(|(_, p)| {
+            let steps = lead.min(game.next_ahead(p.position, p.direction).saturating_sub(1));
+            let mut target = p.position;
+            for _ in 0..steps {
+                target = p.direction.next_position(target);
+            }
+            target
+        });
+    let Some(target) = target else {
+        return space(game, player, budget, trace);
+    };
+
+    best_move(game, player, trace, |next, _| {
+        let room = reachable_area(game, pos, next, CUTTER_MIN_ROOM);
+        (
+            !contested(game, player, next),
+            room >= CUTTER_MIN_ROOM,
+            core::cmp::Reverse(manhattan(next, target)),
+        )
+    })
+}
+
+fn hugger(game: &Game, player: usize, budget: usize, trace: &mut Trace) -> Direction {
+    let pos = game.players[player].position;
+    best_move(game, player, trace, |next, _| {
+        (
+            !contested(game, player, next),
+            reachable_area(game, pos, next, budget),
+            contact(game, next),
+        )
+    })
+}
+
+fn eval(game: &Game, player: usize, weights: &Weights, trace: &mut Trace) -> Direction {
+    best_move(game, player, trace, |next, candidate| {
+        features(game, player, next, candidate, EVAL_BUDGET)
+            .iter()
+            .zip(weights)
+            .map(|(f, w)| f * w)
+            .sum::<i32>()
+    })
+}
+
+/// The features `Strategy::Eval` weighs up for moving to `next`, in the
+/// order of `FEATURE_NAMES`
+pub fn features(
+    game: &Game,
+    player: usize,
+    next: (usize, usize),
+    candidate: Direction,
+    budget: usize,
+) -> [i32; FEATURES] {
+    let pos = game.players[player].position;
+    let opponents = game
+        .players
+        .iter()
+        .enumerate()
+        .filter(|&(i, p)| i != player && p.alive);
+
+    // Us first, so bit 0 of a cell's nearest sources is us
+    let mut sources = [next; 5];
+    let mut count = 1;
+    for (_, p) in opponents.clone().take(sources.len() - 1) {
+        sources[count] = p.position;
+        count += 1;
+    }
+
+    let mut territory = 0usize;
+    if budget != 0 {
+        game.flood(&sources[..count], &[pos], budget, |_, _, nearest| {
+            if nearest == 1 {
+                territory += 1;
+            }
+        });
+    }
+    let opponent = opponents
+        .map(|(_, p)| manhattan(p.position, next))
+        .min()
+        .unwrap_or(0);
+    let sides = game
+        .next_left(next, candidate)
+        .min(game.next_right(next, candidate));
+
+    [
+        reachable_area(game, pos, next, budget) as i32,
+        territory as i32,
+        opponent as i32,
+        contact(game, next) as i32,
+        game.next_ahead(next, candidate) as i32,
+        sides as i32,
+        contested(game, player, next) as i32,
+    ]
+}
+
+/// Counts free cells reachable from start without going back through our own
+/// head (which isn't on the board until the next step), giving up after
+/// budget cells
+fn reachable_area(
+    game: &Game,
+    head: (usize, usize),
+    start: (usize, usize),
+    budget: usize,
+) -> usize {
+    game.flood(&[start], &[head], budget, |_, _, _| {})
+}
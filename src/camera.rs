@@ -0,0 +1,94 @@
+// Which window of the board the 2D view shows. Arenas that fit on screen
+// never scroll, bigger ones follow the local player.
+use crate::game::VIEW_SIZE;
+
+// How close the bike can get to the edge of the view before it scrolls
+const DEAD_ZONE: usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Camera {
+    // Board cell at the top left of the view
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Camera {
+    pub const fn new() -> Self {
+        Camera { row: 0, col: 0 }
+    }
+
+    /// Centres the view on `pos` as far as the arena edges allow
+    pub fn centre(&mut self, pos: (usize, usize), size: usize) {
+        let max = size.saturating_sub(VIEW_SIZE);
+        self.row = pos.0.saturating_sub(VIEW_SIZE / 2).min(max);
+        self.col = pos.1.saturating_sub(VIEW_SIZE / 2).min(max);
+    }
+
+    /// Scrolls just enough to keep `pos` out of the dead zone, returns true
+    /// if the view moved
+    pub fn follow(&mut self, pos: (usize, usize), size: usize) -> bool {
+        let old = *self;
+        self.row = scroll(self.row, pos.0, size);
+        self.col = scroll(self.col, pos.1, size);
+        *self != old
+    }
+
+    /// Where a board cell is in the view, if it's on screen
+    pub fn view_pos(&self, pos: (usize, usize)) -> Option<(usize, usize)> {
+        let row = pos.0.checked_sub(self.row)?;
+        let col = pos.1.checked_sub(self.col)?;
+        (row < VIEW_SIZE && col < VIEW_SIZE).then_some((row, col))
+    }
+}
+
+fn scroll(start: usize, pos: usize, size: usize) -> usize {
+    let start = if pos < start + DEAD_ZONE {
+        pos.saturating_sub(DEAD_ZONE)
+    } else if pos + DEAD_ZONE >= start + VIEW_SIZE {
+        pos + DEAD_ZONE + 1 - VIEW_SIZE
+    } else {
+        start
+    };
+    start.min(size.saturating_sub(VIEW_SIZE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIG: usize = 56;
+
+    #[test]
+    fn small_arenas_never_scroll() {
+        let mut camera = Camera::new();
+        for pos in [(1, 1), (VIEW_SIZE - 2, VIEW_SIZE - 2), (20, 3)] {
+            assert!(!camera.follow(pos, VIEW_SIZE));
+            camera.centre(pos, VIEW_SIZE);
+            assert_eq!(camera, Camera::new());
+        }
+    }
+
+    #[test]
+    fn scrolls_only_out_of_the_dead_zone() {
+        let mut camera = Camera { row: 8, col: 8 };
+        assert!(!camera.follow((8 + DEAD_ZONE, 8 + VIEW_SIZE - DEAD_ZONE - 1), BIG));
+        assert!(camera.follow((8 + DEAD_ZONE - 1, 8), BIG));
+        assert_eq!(camera, Camera { row: 7, col: 0 });
+        assert!(camera.follow((7 + VIEW_SIZE - DEAD_ZONE, 8), BIG));
+        assert_eq!(camera.row, 8);
+    }
+
+    #[test]
+    fn clamps_at_both_arena_edges() {
+        let mut camera = Camera { row: 9, col: 9 };
+        camera.follow((0, 1), BIG);
+        assert_eq!(camera, Camera::new());
+        camera.follow((BIG - 1, BIG - 2), BIG);
+        let far = BIG - VIEW_SIZE;
+        assert_eq!(camera, Camera { row: far, col: far });
+        camera.centre((BIG - 1, 0), BIG);
+        assert_eq!(camera, Camera { row: far, col: 0 });
+        // The bike in the last cell is still on screen
+        assert_eq!(camera.view_pos((BIG - 1, 0)), Some((VIEW_SIZE - 1, 0)));
+    }
+}
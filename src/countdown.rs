@@ -0,0 +1,60 @@
+// 3-2-1-GO before each round. Bikes hold still until GO, but players can
+// already pick the way they'll set off.
+use crate::wasm4::*;
+
+const BEAT: u32 = 40; // frames
+const BEATS: u32 = 3;
+
+pub struct Countdown {
+    frame: u32,
+}
+
+impl Countdown {
+    pub const fn new() -> Self {
+        Countdown {
+            frame: (BEATS + 1) * BEAT + 1,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.frame = 0;
+    }
+
+    /// True until GO, while the simulation is held
+    pub fn holding(&self) -> bool {
+        self.frame <= BEATS * BEAT
+    }
+
+    /// True while there's anything to draw
+    pub fn showing(&self) -> bool {
+        self.frame <= (BEATS + 1) * BEAT
+    }
+
+    /// Advances a frame, beeping on each beat
+    pub fn update(&mut self) {
+        if !self.showing() {
+            return;
+        }
+        if self.frame == BEATS * BEAT {
+            tone(880, 24, 70, TONE_PULSE1);
+        } else if self.frame < BEATS * BEAT && self.frame.is_multiple_of(BEAT) {
+            tone(440, 8, 50, TONE_PULSE1);
+        }
+        self.frame += 1;
+    }
+
+    /// The count in the middle of the board, with GO staying up for a beat
+    /// after the bikes set off
+    pub fn draw(&self, centre: (i32, i32)) {
+        let beat = self.frame.saturating_sub(1) / BEAT;
+        let label = match BEATS.checked_sub(beat) {
+            Some(0) => "GO!".to_string(),
+            Some(n) => n.to_string(),
+            None => return,
+        };
+        unsafe {
+            *DRAW_COLORS = 0x41;
+        }
+        text(&label, centre.0 - 4 * label.len() as i32, centre.1 - 4);
+    }
+}
@@ -0,0 +1,136 @@
+// Crash sequence played between a bike dying and the outcome screen: debris
+// flying out of the impact tile, the dead bike's trail flashing and then
+// dissolving, and a short freeze. Everything lives in fixed-size arrays.
+use crate::camera::Camera;
+use crate::dither::owner_colours;
+use crate::game::{Game, ARENA_SIZE};
+use crate::rng::Rng;
+use crate::wasm4::*;
+use crate::{draw_tile, draw_trail, BOARD_X, BOARD_Y};
+
+const MAX_PARTICLES: usize = 32;
+const DEBRIS_PER_BIKE: usize = 12;
+// Particle positions and speeds are in 1/16ths of a pixel
+const SUBPIXEL: i32 = 16;
+const MAX_SPEED: i32 = 24;
+
+// Timings in frames
+const FLASH_FRAMES: u32 = 40;
+const FLASH_PERIOD: u32 = 4;
+const DISSOLVE_START: u32 = 20;
+const DISSOLVE_FRAMES: u32 = 40;
+const CRASH_FRAMES: u32 = 90;
+
+#[derive(Clone, Copy)]
+struct Particle {
+    x: i32,
+    y: i32,
+    vx: i32,
+    vy: i32,
+    life: u8,
+    colour: u8,
+}
+
+const DEAD_PARTICLE: Particle = Particle {
+    x: 0,
+    y: 0,
+    vx: 0,
+    vy: 0,
+    life: 0,
+    colour: 0,
+};
+
+pub struct Crash {
+    particles: [Particle; MAX_PARTICLES],
+    // Player indices whose trails flash and dissolve
+    crashed: [Option<u8>; 2],
+    frame: u32,
+}
+
+impl Crash {
+    pub const fn new() -> Self {
+        Crash {
+            particles: [DEAD_PARTICLE; MAX_PARTICLES],
+            crashed: [None; 2],
+            frame: CRASH_FRAMES,
+        }
+    }
+
+    /// Starts the sequence for every bike that died on the last step
+    pub fn start(&mut self, game: &Game, camera: &Camera, seed: u32) {
+        let mut rng = Rng::new(seed);
+        *self = Crash::new();
+        self.frame = 0;
+        let mut slots = self.particles.iter_mut();
+        for (i, p) in game.players.iter().enumerate().filter(|(_, p)| !p.alive) {
+            self.crashed[i] = Some(p.index);
+            // Middle of the impact tile, no debris if it's off screen
+            let Some((row, col)) = camera.view_pos(p.position) else {
+                continue;
+            };
+            let x = ((BOARD_X + col * 4) as i32 + 2) * SUBPIXEL;
+            let y = ((BOARD_Y + row * 4) as i32 + 2) * SUBPIXEL;
+            for (n, slot) in slots.by_ref().take(DEBRIS_PER_BIKE).enumerate() {
+                *slot = Particle {
+                    x,
+                    y,
+                    vx: rng.below(2 * MAX_SPEED as u32 + 1) as i32 - MAX_SPEED,
+                    vy: rng.below(2 * MAX_SPEED as u32 + 1) as i32 - MAX_SPEED,
+                    life: 20 + rng.below(20) as u8,
+                    // Mostly the bike's colour with some grey sparks
+                    colour: if n % 3 == 0 {
+                        0
+                    } else {
+                        owner_colours(p.index).0
+                    },
+                };
+            }
+        }
+    }
+
+    /// Advances one frame, returns false once the sequence is over
+    pub fn update(&mut self) -> bool {
+        if self.frame >= CRASH_FRAMES {
+            return false;
+        }
+        self.frame += 1;
+        for p in self.particles.iter_mut().filter(|p| p.life > 0) {
+            p.x += p.vx;
+            p.y += p.vy;
+            // Friction
+            p.vx -= p.vx / 8;
+            p.vy -= p.vy / 8;
+            p.life -= 1;
+        }
+        true
+    }
+
+    /// Draws the crashed trails and debris over an already drawn board
+    pub fn draw(&self, game: &Game, camera: &Camera) {
+        let flash = self.frame < FLASH_FRAMES && (self.frame / FLASH_PERIOD).is_multiple_of(2);
+        let dissolved = self.frame.saturating_sub(DISSOLVE_START);
+        for (ix, tile) in game.board.iter().enumerate() {
+            let Some(c) = tile.filter(|c| self.crashed.contains(&Some(*c))) else {
+                continue;
+            };
+            let Some(pos) = camera.view_pos((ix / ARENA_SIZE, ix % ARENA_SIZE)) else {
+                continue;
+            };
+            // Cells vanish in a fixed pseudo-random order
+            let order = (ix as u32).wrapping_mul(2_654_435_761) >> 26;
+            if order * DISSOLVE_FRAMES / 64 < dissolved {
+                draw_tile(pos, None);
+            } else if flash {
+                draw_trail(pos, 0, game.links[ix]);
+            } else {
+                draw_trail(pos, c, game.links[ix]);
+            }
+        }
+        for p in self.particles.iter().filter(|p| p.life > 0) {
+            unsafe {
+                *DRAW_COLORS = p.colour as u16 + 1;
+            }
+            rect(p.x / SUBPIXEL, p.y / SUBPIXEL, 1, 1);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Board owners in four colours. Walls and the first two bikes get a solid
+// palette colour each, later bikes a checkerboard of two, so every owner
+// stays distinguishable without a fifth colour.
+//
+// Shades in between palette colours use ordered (Bayer) dithering.
+
+/// Number of shades `lit` can tell apart
+pub const LEVELS: u8 = 16;
+
+// 4x4 Bayer matrix, thresholds 0 to 15 spread as evenly as possible
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// The two palette colours an owner's cells alternate between, the same
+/// colour twice for solid owners
+pub const fn owner_colours(owner: u8) -> (u8, u8) {
+    match owner {
+        0 => (0, 0),
+        1 => (1, 1),
+        2 => (2, 2),
+        3 => (1, 2),
+        4 => (2, 0),
+        _ => (3, 3),
+    }
+}
+
+/// Palette colour of screen pixel (x, y) in an owner's pattern
+pub fn owner_pixel(owner: u8, x: usize, y: usize) -> u8 {
+    let (a, b) = owner_colours(owner);
+    if (x + y).is_multiple_of(2) {
+        a
+    } else {
+        b
+    }
+}
+
+/// Whether screen pixel (x, y) is on in an ordered dither that lights
+/// `level` pixels out of every LEVELS
+pub fn lit(level: u8, x: usize, y: usize) -> bool {
+    BAYER[y % 4][x % 4] < level
+}
@@ -0,0 +1,490 @@
+use crate::rng::Rng;
+use core::ptr::addr_of_mut;
+
+// 4x4 size
+// screen 160x160 pixels, less the HUD strip
+// 38x38 units fit on screen, bigger arenas scroll
+pub const VIEW_SIZE: usize = 38;
+/// Board stride, and the size of the biggest arena
+pub const ARENA_SIZE: usize = 56;
+pub const ARENA_CELLS: usize = ARENA_SIZE * ARENA_SIZE;
+// Flood fill scratch, shared by every fill since it's far too big for the
+// WASM-4 stack and no two fills run at once. Bit i of a cell is set when
+// source i is among the nearest to it.
+static mut NEAREST: [u8; ARENA_CELLS] = [0; ARENA_CELLS];
+static mut QUEUE: [u16; ARENA_CELLS] = [0; ARENA_CELLS];
+// Cells on the level being filled, which other sources can still tie for
+const FRONTIER: u8 = 0x80;
+
+// Bits for which sides of a cell its trail connects to
+pub const LINK_NORTH: u8 = 1;
+pub const LINK_EAST: u8 = 2;
+pub const LINK_SOUTH: u8 = 4;
+pub const LINK_WEST: u8 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    pub fn left_turn(&self) -> Self {
+        use Direction::*;
+        match self {
+            North => West,
+            West => South,
+            South => East,
+            East => North,
+        }
+    }
+    pub fn right_turn(&self) -> Self {
+        use Direction::*;
+        match self {
+            North => East,
+            West => North,
+            South => West,
+            East => South,
+        }
+    }
+    pub fn opposite(&self) -> Self {
+        self.left_turn().left_turn()
+    }
+    /// This direction's bit in a cell's trail links
+    pub fn link(&self) -> u8 {
+        use Direction::*;
+        match self {
+            North => LINK_NORTH,
+            South => LINK_SOUTH,
+            East => LINK_EAST,
+            West => LINK_WEST,
+        }
+    }
+    pub fn next_position(&self, pos: (usize, usize)) -> (usize, usize) {
+        use Direction::*;
+        match self {
+            North => (pos.0 - 1, pos.1),
+            South => (pos.0 + 1, pos.1),
+            East => (pos.0, pos.1 + 1),
+            West => (pos.0, pos.1 - 1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Arena {
+    Open,
+    Pillars,
+    Cross,
+    Big,
+}
+
+impl Arena {
+    pub const ALL: [Arena; 4] = [Arena::Open, Arena::Pillars, Arena::Cross, Arena::Big];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Arena::Open => "Open",
+            Arena::Pillars => "Pillars",
+            Arena::Cross => "Cross",
+            Arena::Big => "Big",
+        }
+    }
+
+    /// Width and height in tiles, walls included
+    pub const fn size(self) -> usize {
+        match self {
+            Arena::Big => ARENA_SIZE,
+            _ => VIEW_SIZE,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        use Arena::*;
+        match self {
+            Open => Pillars,
+            Pillars => Cross,
+            Cross => Big,
+            Big => Open,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        use Arena::*;
+        match self {
+            Open => Big,
+            Pillars => Open,
+            Cross => Pillars,
+            Big => Cross,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(b: u8) -> Option<Self> {
+        use Arena::*;
+        match b {
+            0 => Some(Open),
+            1 => Some(Pillars),
+            2 => Some(Cross),
+            3 => Some(Big),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    // First, so Some(Draw) is all zeros
+    Draw,
+    Winner(u8),
+}
+
+#[derive(Clone, Copy)]
+pub struct Player {
+    pub index: u8,
+    pub direction: Direction,
+    pub position: (usize, usize), // (y,x)
+    pub alive: bool,
+}
+
+const fn start_players(size: usize) -> [Player; 2] {
+    [
+        Player {
+            index: 1,
+            direction: Direction::North,
+            position: (size / 2, size * 5 / 8),
+            alive: true,
+        },
+        Player {
+            index: 2,
+            direction: Direction::East,
+            position: (size - 2, size * 2 / 5),
+            alive: true,
+        },
+    ]
+}
+
+#[derive(Clone)]
+pub struct Game {
+    pub board: [Option<u8>; ARENA_CELLS],
+    /// Size of the current arena, everything past it is wall
+    pub size: usize,
+    pub players: [Player; 2],
+    pub outcome: Option<Outcome>,
+    pub ticks: u32,
+    /// Board cells written by the last step, one per player, so the 2D view
+    /// can repaint just those
+    pub changed: [Option<usize>; 2],
+    /// Which neighbours each trail cell connects to, as LINK_* bits
+    pub links: [u8; ARENA_CELLS],
+}
+
+impl Game {
+    /// An empty board with nobody on it, ready for `reset`. That's already
+    /// a draw. It's all zeros, so a static Game takes no room in the cart.
+    pub const fn new() -> Self {
+        const NOBODY: Player = Player {
+            index: 0,
+            direction: Direction::North,
+            position: (0, 0),
+            alive: false,
+        };
+        Game {
+            board: [None; ARENA_CELLS],
+            size: 0,
+            players: [NOBODY; 2],
+            outcome: Some(Outcome::Draw),
+            ticks: 0,
+            changed: [None; 2],
+            links: [0; ARENA_CELLS],
+        }
+    }
+
+    // Field by field, a whole new Game would be built on the stack first
+    pub fn reset(&mut self, arena: Arena) {
+        self.size = arena.size();
+        self.players = start_players(self.size);
+        self.outcome = None;
+        self.ticks = 0;
+        self.changed = [None; 2];
+        self.links.fill(0);
+        self.build_arena(arena);
+    }
+
+    fn build_arena(&mut self, arena: Arena) {
+        let size = self.size;
+        // Walls everywhere, then clear out the inside
+        self.board.fill(Some(0));
+        for r in 1..size - 1 {
+            for c in 1..size - 1 {
+                self.board[r * ARENA_SIZE + c] = None;
+            }
+        }
+        match arena {
+            Arena::Open => {}
+            Arena::Pillars => {
+                for r in [10, size - 12] {
+                    for c in [10, size - 12] {
+                        self.fill((r, c), (r + 1, c + 1));
+                    }
+                }
+            }
+            Arena::Big => {
+                // A grid of pillars to break up the space
+                for r in [size / 4 - 1, size / 2 - 1, size * 3 / 4 - 1] {
+                    for c in [size / 4 - 1, size / 2 - 1, size * 3 / 4 - 1] {
+                        self.fill((r, c), (r + 1, c + 1));
+                    }
+                }
+            }
+            Arena::Cross => {
+                // Bars either side of the middle, two cells thick
+                let (m, far) = (size / 2 - 1, size - 13);
+                self.fill((m, 5), (m + 1, 12));
+                self.fill((m, far), (m + 1, far + 7));
+                self.fill((5, m), (12, m + 1));
+                self.fill((far, m), (far + 7, m + 1));
+            }
+        }
+    }
+
+    // Walls off the inclusive rectangle between two corners
+    fn fill(&mut self, from: (usize, usize), to: (usize, usize)) {
+        for r in from.0..=to.0 {
+            for c in from.1..=to.1 {
+                self.board[r * ARENA_SIZE + c] = Some(0);
+            }
+        }
+    }
+
+    /// Moves every player to a random free cell, facing a random direction
+    /// with at least a few free cells ahead of it
+    pub fn randomise_spawns(&mut self, rng: &mut Rng) {
+        use Direction::*;
+        for i in 0..self.players.len() {
+            loop {
+                let pos = (
+                    1 + rng.below(self.size as u32 - 2) as usize,
+                    1 + rng.below(self.size as u32 - 2) as usize,
+                );
+                let dir = [North, South, East, West][rng.below(4) as usize];
+                let taken = self.players[..i]
+                    .iter()
+                    .any(|p| p.position.0.abs_diff(pos.0) + p.position.1.abs_diff(pos.1) < 8);
+                if !taken && self.is_free(pos) && self.next_ahead(pos, dir) > 4 {
+                    self.players[i].position = pos;
+                    self.players[i].direction = dir;
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn is_free(&self, pos: (usize, usize)) -> bool {
+        self.board[pos.0 * ARENA_SIZE + pos.1].is_none()
+    }
+
+    /// Flood fills from all of `sources` at once (up to 7), treating
+    /// `blocked` as occupied and giving up after `budget` cells. Calls
+    /// `visit` with each cell reached, its distance and which sources are
+    /// nearest to it as bits, and returns how many cells were reached.
+    pub fn flood<F>(
+        &self,
+        sources: &[(usize, usize)],
+        blocked: &[(usize, usize)],
+        budget: usize,
+        mut visit: F,
+    ) -> usize
+    where
+        F: FnMut(usize, u16, u8),
+    {
+        let (nearest, queue) = unsafe { (&mut *addr_of_mut!(NEAREST), &mut *addr_of_mut!(QUEUE)) };
+        nearest.fill(0);
+        let mut tail = 0;
+        for (i, &(r, c)) in sources.iter().enumerate() {
+            let ix = r * ARENA_SIZE + c;
+            if nearest[ix] == 0 {
+                queue[tail] = ix as u16;
+                tail += 1;
+            }
+            nearest[ix] |= 1 << i;
+        }
+
+        // A level at a time, so every tie is in before a level is visited
+        let (mut level, mut dist) = (0, 0);
+        while level < tail {
+            let end = tail;
+            for &ix in &queue[level..end] {
+                visit(ix as usize, dist, nearest[ix as usize]);
+            }
+            for q in level..end {
+                if tail >= budget {
+                    break;
+                }
+                let ix = queue[q] as usize;
+                for next in [ix - ARENA_SIZE, ix + ARENA_SIZE, ix - 1, ix + 1] {
+                    if nearest[next] & FRONTIER != 0 {
+                        nearest[next] |= nearest[ix];
+                    } else if nearest[next] == 0
+                        && self.board[next].is_none()
+                        && !blocked.contains(&(next / ARENA_SIZE, next % ARENA_SIZE))
+                    {
+                        nearest[next] = FRONTIER | nearest[ix];
+                        queue[tail] = next as u16;
+                        tail += 1;
+                    }
+                }
+            }
+            for &ix in &queue[end..tail] {
+                nearest[ix as usize] &= !FRONTIER;
+            }
+            level = end;
+            dist += 1;
+        }
+        tail.min(budget)
+    }
+
+    /// Moves all players at once, so crashing into each other is a draw
+    pub fn step(&mut self) {
+        if self.outcome.is_some() {
+            return;
+        }
+        self.changed = [None; 2];
+        for (i, p) in self.players.iter_mut().enumerate().filter(|(_, p)| p.alive) {
+            let ix = p.position.0 * ARENA_SIZE + p.position.1;
+            self.board[ix] = Some(p.index);
+            self.changed[i] = Some(ix);
+            self.links[ix] |= p.direction.link();
+            p.position = p.direction.next_position(p.position);
+        }
+        let players = self.players;
+        for p in self.players.iter_mut().filter(|p| p.alive) {
+            let head_on = players
+                .iter()
+                .any(|o| o.alive && o.index != p.index && o.position == p.position);
+            if head_on || self.board[p.position.0 * ARENA_SIZE + p.position.1].is_some() {
+                p.alive = false;
+            } else {
+                // Link the new head back to the cell it came from
+                self.links[p.position.0 * ARENA_SIZE + p.position.1] |=
+                    p.direction.opposite().link();
+            }
+        }
+        self.ticks += 1;
+
+        let mut alive = self.players.iter().filter(|p| p.alive);
+        self.outcome = match (alive.next(), alive.next()) {
+            (None, _) => Some(Outcome::Draw),
+            (Some(p), None) => Some(Outcome::Winner(p.index)),
+            _ => None,
+        };
+    }
+
+    /// Distance to the first occupied cell in the given direction
+    pub fn next_ahead(&self, pos: (usize, usize), dir: Direction) -> usize {
+        use Direction::*;
+        let diff: i32 = match dir {
+            North => -(ARENA_SIZE as i32),
+            East => 1,
+            West => -1,
+            South => ARENA_SIZE as i32,
+        };
+        let mut ix = (pos.0 * ARENA_SIZE) + pos.1;
+        for z in 1..=ARENA_SIZE {
+            ix = (ix as i32 + diff) as usize;
+            if self.board[ix].is_some() {
+                return z;
+            }
+        }
+        0
+    }
+
+    pub fn next_left(&self, pos: (usize, usize), dir: Direction) -> usize {
+        self.next_ahead(pos, dir.left_turn())
+    }
+
+    pub fn next_right(&self, pos: (usize, usize), dir: Direction) -> usize {
+        self.next_ahead(pos, dir.right_turn())
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A cell a flood fill reached, its distance and nearest sources
+    type Reached = ((usize, usize), u16, u8);
+
+    fn fill(
+        game: &Game,
+        sources: &[(usize, usize)],
+        blocked: &[(usize, usize)],
+        budget: usize,
+    ) -> (usize, Vec<Reached>) {
+        let mut cells = Vec::new();
+        let count = game.flood(sources, blocked, budget, |ix, dist, nearest| {
+            cells.push(((ix / ARENA_SIZE, ix % ARENA_SIZE), dist, nearest));
+        });
+        (count, cells)
+    }
+
+    fn find(cells: &[Reached], pos: (usize, usize)) -> Option<(u16, u8)> {
+        cells.iter().find(|c| c.0 == pos).map(|c| (c.1, c.2))
+    }
+
+    // One test, since every fill shares the same static scratch
+    #[test]
+    fn flood() {
+        let mut game = Game::new();
+        game.reset(Arena::Open);
+        let inside = (VIEW_SIZE - 2) * (VIEW_SIZE - 2);
+
+        // Everything inside the walls, each cell once
+        let (count, cells) = fill(&game, &[(10, 10)], &[], ARENA_CELLS);
+        assert_eq!(count, inside);
+        assert_eq!(cells.len(), inside);
+        assert_eq!(find(&cells, (10, 10)), Some((0, 1)));
+        assert_eq!(find(&cells, (13, 6)), Some((7, 1)));
+        assert_eq!(find(&cells, (0, 10)), None);
+
+        // Two sources on a row: the cell between them is a tie, either
+        // side belongs to the nearer one
+        let (_, cells) = fill(&game, &[(10, 10), (10, 14)], &[], ARENA_CELLS);
+        assert_eq!(find(&cells, (10, 12)), Some((2, 0b11)));
+        assert_eq!(find(&cells, (20, 12)), Some((12, 0b11)));
+        assert_eq!(find(&cells, (10, 11)), Some((1, 0b01)));
+        assert_eq!(find(&cells, (10, 13)), Some((1, 0b10)));
+        assert_eq!(find(&cells, (1, 1)), Some((18, 0b01)));
+
+        // Blocked cells are never reached, and fills go round them
+        let (count, cells) = fill(&game, &[(10, 10)], &[(10, 11), (9, 11)], ARENA_CELLS);
+        assert_eq!(count, inside - 2);
+        assert_eq!(find(&cells, (10, 11)), None);
+        assert_eq!(find(&cells, (10, 12)), Some((4, 1)));
+
+        // The budget stops the fill partway through a level
+        let (count, cells) = fill(&game, &[(10, 10)], &[], 10);
+        assert_eq!(count, 10);
+        assert!(cells.len() >= 10 && cells.len() < 13);
+        assert!(cells.iter().all(|c| c.1 <= 2));
+        let (count, cells) = fill(&game, &[(10, 10)], &[], 0);
+        assert_eq!(count, 0);
+        assert_eq!(cells.len(), 1);
+
+        // Walled off cells aren't reached
+        game.fill((5, 1), (5, VIEW_SIZE - 2));
+        let (count, _) = fill(&game, &[(2, 2)], &[], ARENA_CELLS);
+        assert_eq!(count, 4 * (VIEW_SIZE - 2));
+    }
+}
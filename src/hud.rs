@@ -0,0 +1,61 @@
+use crate::wasm4::{rect, text, DRAW_COLORS, SCREEN_SIZE};
+
+/// Height of the strip along the top of the screen
+pub const HUD_HEIGHT: usize = 8;
+
+pub struct HudInfo {
+    /// Rounds won by each player
+    pub scores: [u32; 2],
+    pub round: u32,
+    /// Frames played this round
+    pub frames: u32,
+    /// Who player 1 is up against, the AI difficulty or "2P"
+    pub opponent: &'static str,
+}
+
+impl HudInfo {
+    /// A fresh match against `opponent`
+    pub const fn new(opponent: &'static str) -> Self {
+        HudInfo {
+            scores: [0; 2],
+            round: 1,
+            frames: 0,
+            opponent,
+        }
+    }
+}
+
+/// Score, round, clock and opponent along the top of the screen, drawn
+/// over whatever view is showing
+pub fn draw_hud(info: &HudInfo) {
+    unsafe {
+        *DRAW_COLORS = 0x4;
+    }
+    rect(0, 0, SCREEN_SIZE, HUD_HEIGHT as u32);
+
+    // Each score in its bike's colour, grey for the rest
+    let mut x = 0;
+    for (i, score) in info.scores.iter().enumerate() {
+        if i > 0 {
+            unsafe {
+                *DRAW_COLORS = 0x41;
+            }
+            text("-", x, 0);
+            x += 8;
+        }
+        unsafe {
+            *DRAW_COLORS = 0x40 | (i as u16 + 2);
+        }
+        let score = score.to_string();
+        text(&score, x, 0);
+        x += 8 * score.len() as i32;
+    }
+    unsafe {
+        *DRAW_COLORS = 0x41;
+    }
+    text(format!("R{}", info.round), 40, 0);
+    let seconds = info.frames / 60;
+    text(format!("{}:{:02}", seconds / 60, seconds % 60), 72, 0);
+    let name = info.opponent;
+    text(name, SCREEN_SIZE as i32 - 8 * name.len() as i32, 0);
+}
@@ -1,7 +1,38 @@
-#[cfg(feature = "buddy-alloc")]
+pub mod ai;
+#[cfg(all(feature = "buddy-alloc", target_arch = "wasm32"))]
 mod alloc;
+mod camera;
+mod countdown;
+mod crash;
+mod dither;
+pub mod game;
+mod hud;
+mod menu;
+mod minimap;
+mod overhead;
+#[cfg(feature = "ai-debug")]
+mod overlay;
+mod radar;
 mod raycast;
+pub mod rng;
+mod settings;
+mod sprites;
+mod theme;
+mod transition;
+mod trig;
+mod viewport;
+#[allow(clippy::manual_dangling_ptr)]
 mod wasm4;
+pub mod weights;
+use ai::Ai;
+use camera::Camera;
+use core::ptr::{addr_of, addr_of_mut};
+use dither::{owner_colours, owner_pixel};
+use game::{Direction, Game, Outcome, ARENA_SIZE, VIEW_SIZE};
+use menu::Menu;
+use settings::{Players, Settings};
+use transition::{Effect, Transition};
+use viewport::Viewport;
 use wasm4::*;
 
 pub fn set_palette(palette: [u32; 4]) {
@@ -10,249 +41,478 @@ pub fn set_palette(palette: [u32; 4]) {
     }
 }
 
+// Frames for each half of a fade between screens
+const FADE_FRAMES: u32 = 15;
+// Frames between game steps
+const STEP_FRAMES: u8 = 9;
+
 #[derive(Clone, Copy)]
-pub enum Direction {
-    North,
-    South,
-    East,
-    West,
+enum DrawMode {
+    TwoD,
+    Chase,
+    ThreeD,
+}
+
+enum Screen {
+    Title,
+    Playing,
+}
+
+static mut SCREEN: Screen = Screen::Title;
+static mut SETTINGS: Settings = Settings::new();
+static mut MENU: Menu = Menu::new();
+static mut AI: Ai = Ai::new(ai::Difficulty::Normal.params(), 0);
+static mut TICKS: u32 = 0;
+// Each player's view, the second only matters in split-screen
+static mut DRAWMODE: [DrawMode; 2] = [DrawMode::TwoD; 2];
+static mut PREV_GAMEPAD: [u8; 2] = [0; 2];
+static mut FRAME: u8 = 0;
+static mut GAME: Game = Game::new();
+static mut CRASH: crash::Crash = crash::Crash::new();
+static mut CAMERA: Camera = Camera::new();
+static mut VIEW_ANGLE: [raycast::ViewAngle; 2] =
+    [raycast::ViewAngle::new(), raycast::ViewAngle::new()];
+static mut COUNTDOWN: countdown::Countdown = countdown::Countdown::new();
+static mut TRANSITION: Transition = Transition::new();
+// Screen to switch to once the current fade out finishes
+static mut NEXT_SCREEN: Option<Screen> = None;
+static mut HUD: hud::HudInfo = hud::HudInfo::new("");
+// Set when the 2D view needs a full repaint rather than just changed cells
+static mut REDRAW: bool = true;
+#[cfg(feature = "ai-debug")]
+static mut OVERLAY: overlay::Overlay = overlay::Overlay::new();
+
+// The board sits under the HUD strip, centred across the screen
+pub const BOARD_X: usize = (SCREEN_SIZE as usize - VIEW_SIZE * 4) / 2;
+pub const BOARD_Y: usize = hud::HUD_HEIGHT;
+
+/// Index of the framebuffer byte holding the top row of a tile in the view
+pub fn tile_byte(view_pos: (usize, usize)) -> usize {
+    let (row, col) = view_pos;
+    ((BOARD_Y + row * 4) * 40) + BOARD_X / 4 + col
 }
 
-impl Direction {
-    pub fn left_turn(&self) -> Self {
-        use Direction::*;
-        match self {
-            North => West,
-            West => South,
-            South => East,
-            East => North,
+/// Fills a tile with a board cell's owner pattern, None being floor
+pub fn draw_tile(view_pos: (usize, usize), cell: Option<u8>) {
+    let (row, col) = view_pos;
+    let byte = tile_byte(view_pos);
+    for r in 0..4 {
+        // The framebuffer keeps the leftmost pixel in the low bits
+        let mut pixels = 0;
+        for px in 0..4 {
+            let colour = match cell {
+                Some(owner) => owner_pixel(owner, col * 4 + px, row * 4 + r),
+                None => 3,
+            };
+            pixels |= colour << (2 * px);
         }
-    }
-    pub fn right_turn(&self) -> Self {
-        use Direction::*;
-        match self {
-            North => East,
-            West => North,
-            South => West,
-            East => South,
+        unsafe {
+            (*FRAMEBUFFER)[byte + r * 40] = pixels;
         }
     }
 }
 
-enum DrawMode {
-    TwoD,
-    ThreeD,
+/// Draws a trail cell as a line in `owner`'s pattern joining the sides in
+/// `links`
+pub fn draw_trail(view_pos: (usize, usize), owner: u8, links: u8) {
+    let (row, col) = view_pos;
+    let byte = tile_byte(view_pos);
+    let tile = &sprites::TRAIL_TILES[links as usize];
+    for (r, bits) in tile.iter().enumerate() {
+        // The framebuffer keeps the leftmost pixel in the low bits
+        let mut pixels = 0;
+        for px in 0..4 {
+            let colour = if bits & (0b1000 >> px) != 0 {
+                owner_pixel(owner, col * 4 + px, row * 4 + r)
+            } else {
+                3
+            };
+            pixels |= colour << (2 * px);
+        }
+        unsafe {
+            (*FRAMEBUFFER)[byte + r * 40] = pixels;
+        }
+    }
 }
 
-static mut DRAWMODE: DrawMode = DrawMode::TwoD;
-static mut WINNER: Option<u8> = None;
-static mut PREV_GAMEPAD: u8 = 0;
-static mut FRAME: u8 = 0;
-static mut BOARD: [Option<u8>; 1600] = [None; 1600];
-static mut PLAYERS: [Player; 2] = [
-    Player {
-        index: 1,
-        direction: Direction::North,
-        position: (20, 25),
-    },
-    Player {
-        index: 2,
-        direction: Direction::East,
-        position: (38, 16),
-    },
-];
+/// Draws a board cell if it's in view
+fn draw_cell(game: &Game, camera: &Camera, pos: (usize, usize)) {
+    let Some(view_pos) = camera.view_pos(pos) else {
+        return;
+    };
+    let ix = pos.0 * ARENA_SIZE + pos.1;
+    match game.board[ix] {
+        None | Some(0) => {
+            draw_tile(view_pos, game.board[ix]);
+        }
+        Some(x) => {
+            draw_trail(view_pos, x, game.links[ix]);
+        }
+    }
+}
 
-pub fn build_arena() {
+pub fn draw_board(game: &Game, camera: &Camera) {
+    // Margins either side of the board
     unsafe {
-        // Columns
-        for r in 0..40 {
-            BOARD[r * 40] = Some(0);
-            BOARD[r * 40 + 39] = Some(0);
-        }
-        // Top and bottom row
-        for c in 1..39 {
-            BOARD[c] = Some(0);
-            BOARD[40 * 39 + c] = Some(0);
+        *DRAW_COLORS = 0x4;
+    }
+    rect(0, BOARD_Y as i32, BOARD_X as u32, SCREEN_SIZE);
+    rect(
+        (SCREEN_SIZE as usize - BOARD_X) as i32,
+        BOARD_Y as i32,
+        BOARD_X as u32,
+        SCREEN_SIZE,
+    );
+    for row in camera.row..camera.row + VIEW_SIZE {
+        for col in camera.col..camera.col + VIEW_SIZE {
+            draw_cell(game, camera, (row, col));
         }
     }
 }
 
-// 4x4 size
-// screen 160x160 pixels
-// 40x40 units
-pub struct Player {
-    index: u8,
-    direction: Direction,
-    position: (usize, usize), // (y,x)
+/// Repaints only the cells the last step wrote, the rest of the board is
+/// still in the preserved framebuffer
+pub fn draw_changed(game: &Game, camera: &Camera) {
+    for ix in game.changed.iter().flatten() {
+        draw_cell(game, camera, (ix / ARENA_SIZE, ix % ARENA_SIZE));
+    }
 }
 
-pub fn draw_tile(board_pos: (usize, usize), c: u8) {
-    let (row, col) = board_pos;
-    unsafe {
-        for r in 0..4 {
-            (*FRAMEBUFFER)[(((row * 4) + r) * 40) + col] = (c << 6) | (c << 4) | (c << 2) | c;
-            // (*FRAMEBUFFER)[(((row * 8) + r) * 2 * 20) + (col * 2) + 1] =
-            //     (c << 6) | (c << 4) | (c << 2) | c;
+pub fn draw_players(game: &Game, camera: &Camera) {
+    // Crashed bikes are left to the crash sequence
+    for p in game.players.iter().filter(|p| p.alive) {
+        let Some((row, col)) = camera.view_pos(p.position) else {
+            continue;
+        };
+        // Repaint what's under the head, the sprite's corners are transparent
+        draw_cell(game, camera, p.position);
+        unsafe {
+            *DRAW_COLORS = 0x400 | ((owner_colours(p.index).0 as u16 + 1) << 4);
         }
+        blit_facing(&sprites::BIKE, (row, col), p.direction);
     }
 }
 
-pub fn draw_board() {
-    unsafe {
-        for (ix, tile) in BOARD.iter().enumerate() {
-            let row = ix / 40;
-            let col = ix % 40;
-            match tile {
-                None => {
-                    draw_tile((row, col), 3);
-                }
-                Some(x) => {
-                    draw_tile((row, col), *x);
-                }
+/// Heading arrows in front of each bike, for the countdown
+pub fn draw_arrows(game: &Game, camera: &Camera) {
+    for p in &game.players {
+        let ahead = p.direction.next_position(p.position);
+        if let Some(view_pos) = camera.view_pos(ahead) {
+            unsafe {
+                *DRAW_COLORS = (owner_colours(p.index).0 as u16 + 1) << 4;
             }
+            blit_facing(&sprites::ARROW, view_pos, p.direction);
         }
     }
 }
 
-pub fn draw_players() {
-    unsafe {
-        for p in &PLAYERS {
-            draw_tile(p.position, p.index);
-        }
+/// Blit flags turning a north-facing sprite to face `dir`
+pub fn facing_flags(dir: Direction) -> u32 {
+    // BLIT_ROTATE turns counter-clockwise, flips apply to the sprite before
+    // it, so east is south turned
+    match dir {
+        Direction::North => 0,
+        Direction::South => BLIT_FLIP_Y,
+        Direction::West => BLIT_ROTATE,
+        Direction::East => BLIT_ROTATE | BLIT_FLIP_Y,
     }
 }
 
-pub fn input() {
+// Blits a one-tile, north-facing 2bpp sprite turned to face `dir`
+fn blit_facing(sprite: &[u8], view_pos: (usize, usize), dir: Direction) {
+    let (row, col) = view_pos;
+    blit(
+        sprite,
+        (BOARD_X + col * sprites::BIKE_SIZE as usize) as i32,
+        (BOARD_Y + row * sprites::BIKE_SIZE as usize) as i32,
+        sprites::BIKE_SIZE,
+        sprites::BIKE_SIZE,
+        BLIT_2BPP | facing_flags(dir),
+    );
+}
+
+pub fn input(game: &mut Game, i: usize, just_pressed: u8) {
+    let player = &mut game.players[i];
     unsafe {
-        let gamepad = *wasm4::GAMEPAD1;
-        let just_pressed = gamepad & (gamepad ^ PREV_GAMEPAD);
         if just_pressed & wasm4::BUTTON_LEFT != 0 {
-            PLAYERS[0].direction = PLAYERS[0].direction.left_turn();
+            player.direction = player.direction.left_turn();
         } else if just_pressed & wasm4::BUTTON_RIGHT != 0 {
-            PLAYERS[0].direction = PLAYERS[0].direction.right_turn();
+            player.direction = player.direction.right_turn();
         } else if just_pressed & wasm4::BUTTON_UP != 0 {
-            DRAWMODE = match DRAWMODE {
-                DrawMode::TwoD => DrawMode::ThreeD,
+            DRAWMODE[i] = match DRAWMODE[i] {
+                DrawMode::TwoD => {
+                    (*addr_of_mut!(VIEW_ANGLE))[i].snap(player.direction);
+                    DrawMode::Chase
+                }
+                DrawMode::Chase => DrawMode::ThreeD,
                 DrawMode::ThreeD => DrawMode::TwoD,
             };
+            REDRAW = true;
         }
-
-        PREV_GAMEPAD = gamepad;
     }
 }
 
-pub fn step() {
+pub fn reset_round() {
     unsafe {
-        for p in &mut PLAYERS {
-            use Direction::*;
-            BOARD[p.position.0 * 40 + p.position.1] = Some(p.index);
-            match p.direction {
-                North => p.position = (p.position.0 - 1, p.position.1),
-                South => p.position = (p.position.0 + 1, p.position.1),
-                East => p.position = (p.position.0, p.position.1 + 1),
-                West => p.position = (p.position.0, p.position.1 - 1),
-            }
-            if BOARD[p.position.0 * 40 + p.position.1].is_some() {
-                // TODO: Handle draw - i.e. don't move P1 first
-                WINNER = if p.index == 1 { Some(2) } else { Some(1) };
-            }
-            // If player heads collide, AI wins
-            if (p.index == 1 && p.position == PLAYERS[1].position)
-                || (p.index == 2 && p.position == PLAYERS[0].position)
-            {
-                WINNER = Some(2);
-            }
-
-            // draw_players();
+        let game = &mut *addr_of_mut!(GAME);
+        game.reset(SETTINGS.arena);
+        (*addr_of_mut!(CAMERA)).centre(game.players[0].position, game.size);
+        for (angle, p) in (*addr_of_mut!(VIEW_ANGLE)).iter_mut().zip(game.players) {
+            angle.snap(p.direction);
         }
+        FRAME = 0;
+        REDRAW = true;
+        HUD.frames = 0;
+        (*addr_of_mut!(COUNTDOWN)).start();
+        let params = SETTINGS.difficulty.params();
+        AI = Ai::new(params.with_personality(SETTINGS.personality), TICKS);
     }
 }
 
-pub fn next_ahead(pos: (usize, usize), dir: Direction) -> usize {
-    use Direction::*;
-    let diff: i32 = match dir {
-        North => -40,
-        East => 1,
-        West => -1,
-        South => 40,
-    };
-    let mut ix = (pos.0 * 40) + pos.1;
-    for z in 1..=40 {
-        ix = (ix as i32 + diff) as usize;
-        unsafe {
-            if BOARD[ix as usize].is_some() {
-                return z as usize;
-            }
+fn title(just_pressed: u8) {
+    unsafe {
+        let settings = &mut *addr_of_mut!(SETTINGS);
+        if (*addr_of_mut!(MENU)).update(settings, just_pressed) {
+            settings.save();
+            switch_to(Screen::Playing);
         }
     }
-    0
-}
-
-pub fn next_left(pos: (usize, usize), dir: Direction) -> usize {
-    next_ahead(pos, dir.left_turn())
 }
 
-pub fn next_right(pos: (usize, usize), dir: Direction) -> usize {
-    next_ahead(pos, dir.right_turn())
+// Fades out, then enters `screen` and fades back in
+fn switch_to(screen: Screen) {
+    unsafe {
+        NEXT_SCREEN = Some(screen);
+        (*addr_of_mut!(TRANSITION)).start(Effect::FadeOut, FADE_FRAMES);
+    }
 }
 
-pub fn ai() {
+fn enter(screen: Screen) {
     unsafe {
-        let pos = PLAYERS[1].position;
-        let dir = PLAYERS[1].direction;
-        let na = next_ahead(pos, dir);
-        if na > 3 {
-            return;
-        }
-        let nl = next_left(pos, dir);
-        let nr = next_right(pos, dir);
-        if nl >= nr && nl > na {
-            PLAYERS[1].direction = PLAYERS[1].direction.left_turn();
-        }
-        if nr >= nl && nr > na {
-            PLAYERS[1].direction = PLAYERS[1].direction.right_turn();
+        match screen {
+            Screen::Playing => {
+                HUD = hud::HudInfo::new(match SETTINGS.players {
+                    Players::One => SETTINGS.difficulty.name(),
+                    Players::Two => "2P",
+                });
+                reset_round();
+                // Only changed cells are drawn in play, so keep the rest around
+                *SYSTEM_FLAGS |= SYSTEM_PRESERVE_FRAMEBUFFER;
+            }
+            Screen::Title => {
+                *SYSTEM_FLAGS &= !SYSTEM_PRESERVE_FRAMEBUFFER;
+            }
         }
+        SCREEN = screen;
     }
 }
 
-#[no_mangle]
-fn start() {
-    set_palette([0x686c73, 0x1e88e5, 0xffc107, 0x000000]);
-    build_arena();
+// One player's view into a viewport, for split-screen. `arrows` shows
+// headings during the countdown in the top-down view.
+fn draw_view(game: &Game, i: usize, viewport: Viewport, progress: i32, arrows: bool) {
+    unsafe {
+        match DRAWMODE[i] {
+            DrawMode::TwoD => overhead::draw_overhead(game, i, viewport, arrows),
+            DrawMode::Chase | DrawMode::ThreeD => {
+                let pov = match DRAWMODE[i] {
+                    DrawMode::Chase => raycast::Pov::Chase,
+                    _ => raycast::Pov::FirstPerson,
+                };
+                let view_angle = &mut (*addr_of_mut!(VIEW_ANGLE))[i];
+                view_angle.update(game.players[i].direction);
+                raycast::draw_3d(
+                    game,
+                    i,
+                    pov,
+                    view_angle.angle(),
+                    progress,
+                    SETTINGS.fov,
+                    viewport,
+                );
+                radar::draw_radar(game, i, view_angle.angle(), pov.fov(SETTINGS.fov), viewport);
+            }
+        }
+    }
 }
 
-#[no_mangle]
-fn update() {
+fn play(just_pressed: [u8; 2]) {
     unsafe {
-        if let Some(w) = &WINNER {
-            match w {
-                1 => {
+        let game = &mut *addr_of_mut!(GAME);
+        if let Some(outcome) = game.outcome {
+            let crash = &mut *addr_of_mut!(CRASH);
+            if crash.update() {
+                // The crash plays out top-down whichever view is on
+                let camera = &*addr_of!(CAMERA);
+                draw_board(game, camera);
+                draw_players(game, camera);
+                crash.draw(game, camera);
+                if game.size > VIEW_SIZE {
+                    minimap::draw_minimap(game, camera);
+                }
+                hud::draw_hud(&*addr_of!(HUD));
+                return;
+            }
+            // The last crash frame stays frozen under the message
+            *DRAW_COLORS = 0x41;
+            match outcome {
+                Outcome::Winner(i) if SETTINGS.players == Players::Two => {
+                    text(format!("P{} wins!", i), 44, 80);
+                }
+                Outcome::Winner(1) => {
                     text("You won!", 40, 80);
                 }
-                2 => {
+                Outcome::Winner(_) => {
                     text("You lost!", 40, 80);
                 }
-                _ => {}
+                Outcome::Draw => {
+                    text("It's a draw!", 32, 80);
+                }
+            }
+            text("X: next round", 28, 96);
+            text("Z: title", 48, 106);
+            // Either player can carry on
+            let just_pressed = just_pressed[0] | just_pressed[1];
+            if just_pressed & BUTTON_1 != 0 {
+                HUD.round += 1;
+                reset_round();
+                (*addr_of_mut!(TRANSITION)).start(Effect::Wipe, 20);
+            } else if just_pressed & BUTTON_2 != 0 {
+                switch_to(Screen::Title);
             }
             return;
         };
-        match DRAWMODE {
-            DrawMode::TwoD => {
-                draw_board();
-                draw_players();
+        let countdown = &mut *addr_of_mut!(COUNTDOWN);
+        countdown.update();
+        // The step comes at the end of frame 0, so that's when a bike has
+        // gone the whole way to its next tile
+        let progress = if FRAME == 0 {
+            trig::ONE
+        } else {
+            FRAME as i32 * trig::ONE / STEP_FRAMES as i32
+        };
+        match (SETTINGS.players, DRAWMODE[0]) {
+            (Players::Two, _) => {
+                for (i, viewport) in Viewport::SPLIT.into_iter().enumerate() {
+                    draw_view(game, i, viewport, progress, countdown.holding());
+                }
+                // Kept up to date for the crash, which plays full screen
+                (*addr_of_mut!(CAMERA)).follow(game.players[0].position, game.size);
+                *DRAW_COLORS = 0x1;
+                rect(SCREEN_SIZE as i32 / 2 - 1, BOARD_Y as i32, 2, SCREEN_SIZE);
             }
-            DrawMode::ThreeD => {
-                raycast::draw_3d(PLAYERS[0].position, PLAYERS[0].direction);
+            (Players::One, DrawMode::TwoD) => {
+                let camera = &mut *addr_of_mut!(CAMERA);
+                // Scrolling moves every tile
+                REDRAW |= camera.follow(game.players[0].position, game.size);
+                #[cfg(feature = "ai-debug")]
+                {
+                    REDRAW |= (*addr_of!(OVERLAY)).active();
+                }
+                if REDRAW {
+                    draw_board(game, camera);
+                    REDRAW = false;
+                } else {
+                    draw_changed(game, camera);
+                }
+                draw_players(game, camera);
+                if countdown.holding() {
+                    draw_arrows(game, camera);
+                }
+                // Wipe the arrows and count next frame
+                REDRAW |= countdown.showing();
+                if game.size > VIEW_SIZE {
+                    minimap::draw_minimap(game, camera);
+                }
+                #[cfg(feature = "ai-debug")]
+                {
+                    let overlay = &*addr_of!(OVERLAY);
+                    let trace = (overlay.subject() == 1).then(|| &(*addr_of!(AI)).trace);
+                    overlay.draw(game, camera, trace);
+                    // The overlay would otherwise stay in the framebuffer
+                    REDRAW |= overlay.active();
+                }
             }
+            (Players::One, DrawMode::Chase | DrawMode::ThreeD) => {
+                draw_view(game, 0, Viewport::MAIN, progress, false);
+            }
+        }
+        countdown.draw((80, (BOARD_Y + VIEW_SIZE * 2) as i32));
+        if !countdown.holding() {
+            HUD.frames += 1;
+        }
+        hud::draw_hud(&*addr_of!(HUD));
+        input(game, 0, just_pressed[0]);
+        if SETTINGS.players == Players::Two {
+            input(game, 1, just_pressed[1]);
+        }
+        #[cfg(feature = "ai-debug")]
+        (*addr_of_mut!(OVERLAY)).input(game, just_pressed[0]);
+        // Turns are allowed during the countdown but nobody moves until GO
+        if countdown.holding() {
+            return;
         }
-        input();
-        ai();
         if FRAME == 0 {
-            step();
+            if SETTINGS.players == Players::One {
+                let ai = &mut *addr_of_mut!(AI);
+                game.players[1].direction = ai.think(game, 1);
+                #[cfg(feature = "ai-debug")]
+                (*addr_of!(OVERLAY)).log(game, 1, &ai.trace);
+            }
+            game.step();
+            if let Some(outcome) = game.outcome {
+                if let Outcome::Winner(i) = outcome {
+                    HUD.scores[i as usize - 1] += 1;
+                }
+                (*addr_of_mut!(CRASH)).start(game, &*addr_of!(CAMERA), TICKS);
+                (*addr_of_mut!(TRANSITION)).start(Effect::Flash, 12);
+            }
         }
         FRAME += 1;
-        if FRAME > 8 {
+        if FRAME >= STEP_FRAMES {
             FRAME = 0
         };
     }
 }
+
+#[no_mangle]
+fn start() {
+    unsafe {
+        SETTINGS = Settings::load();
+        set_palette(SETTINGS.theme.palette());
+    }
+}
+
+#[no_mangle]
+fn update() {
+    unsafe {
+        TICKS = TICKS.wrapping_add(1);
+        let gamepads = [*GAMEPAD1, *GAMEPAD2];
+        let prev = PREV_GAMEPAD;
+        let mut just_pressed = [0, 1].map(|i| gamepads[i] & (gamepads[i] ^ prev[i]));
+        PREV_GAMEPAD = gamepads;
+        // Nothing to press on a screen that's on its way out
+        if (*addr_of!(NEXT_SCREEN)).is_some() {
+            just_pressed = [0; 2];
+        }
+        match SCREEN {
+            Screen::Title => title(just_pressed[0]),
+            Screen::Playing => play(just_pressed),
+        }
+        let transition = &mut *addr_of_mut!(TRANSITION);
+        // Follows the theme setting live, transitions work from it too
+        let palette = SETTINGS.theme.palette();
+        if !transition.active() {
+            set_palette(palette);
+        }
+        if transition.update(palette) {
+            if let Some(screen) = (*addr_of_mut!(NEXT_SCREEN)).take() {
+                enter(screen);
+                transition.start(Effect::FadeIn, FADE_FRAMES);
+            }
+        }
+        // Effects draw over the preserved framebuffer
+        if transition.active() {
+            REDRAW = true;
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::raycast::{FOV_MAX, FOV_MIN, FOV_STEP};
+use crate::settings::Settings;
+use crate::wasm4::*;
+
+#[derive(Clone, Copy)]
+enum Item {
+    Players,
+    Difficulty,
+    Personality,
+    Arena,
+    Theme,
+    Fov,
+}
+
+const ITEMS: [Item; 6] = [
+    Item::Players,
+    Item::Difficulty,
+    Item::Personality,
+    Item::Arena,
+    Item::Theme,
+    Item::Fov,
+];
+const FIRST_ROW: i32 = 22;
+const ROW_HEIGHT: i32 = 20;
+
+impl Item {
+    fn label(self) -> &'static str {
+        match self {
+            Item::Players => "Players",
+            Item::Difficulty => "AI difficulty",
+            Item::Personality => "AI personality",
+            Item::Arena => "Arena",
+            Item::Theme => "Theme",
+            Item::Fov => "3D field of view",
+        }
+    }
+
+    fn value(self, settings: &Settings) -> String {
+        match self {
+            Item::Players => settings.players.name().into(),
+            Item::Difficulty => settings.difficulty.name().into(),
+            Item::Personality => settings.personality.name().into(),
+            Item::Arena => settings.arena.name().into(),
+            Item::Theme => settings.theme.name().into(),
+            Item::Fov => format!("{}", settings.fov),
+        }
+    }
+
+    fn change(self, settings: &mut Settings, forward: bool) {
+        match (self, forward) {
+            (Item::Players, _) => settings.players = settings.players.toggle(),
+            (Item::Difficulty, true) => settings.difficulty = settings.difficulty.next(),
+            (Item::Difficulty, false) => settings.difficulty = settings.difficulty.prev(),
+            (Item::Personality, true) => settings.personality = settings.personality.next(),
+            (Item::Personality, false) => settings.personality = settings.personality.prev(),
+            (Item::Arena, true) => settings.arena = settings.arena.next(),
+            (Item::Arena, false) => settings.arena = settings.arena.prev(),
+            (Item::Theme, true) => settings.theme = settings.theme.next(),
+            (Item::Theme, false) => settings.theme = settings.theme.prev(),
+            // Wraps around like the others
+            (Item::Fov, true) if settings.fov >= FOV_MAX => settings.fov = FOV_MIN,
+            (Item::Fov, true) => settings.fov += FOV_STEP,
+            (Item::Fov, false) if settings.fov <= FOV_MIN => settings.fov = FOV_MAX,
+            (Item::Fov, false) => settings.fov -= FOV_STEP,
+        }
+    }
+}
+
+pub struct Menu {
+    cursor: usize,
+}
+
+impl Menu {
+    pub const fn new() -> Self {
+        Menu { cursor: 0 }
+    }
+
+    /// Handles input and draws the title screen, returns true when the
+    /// player starts a round
+    pub fn update(&mut self, settings: &mut Settings, just_pressed: u8) -> bool {
+        if just_pressed & BUTTON_UP != 0 {
+            self.cursor = (self.cursor + ITEMS.len() - 1) % ITEMS.len();
+        } else if just_pressed & BUTTON_DOWN != 0 {
+            self.cursor = (self.cursor + 1) % ITEMS.len();
+        } else if just_pressed & BUTTON_LEFT != 0 {
+            ITEMS[self.cursor].change(settings, false);
+        } else if just_pressed & BUTTON_RIGHT != 0 {
+            ITEMS[self.cursor].change(settings, true);
+        } else if just_pressed & BUTTON_1 != 0 {
+            return true;
+        }
+
+        unsafe {
+            *DRAW_COLORS = 0x02;
+        }
+        text("W4TRON", 56, 6);
+        for (i, item) in ITEMS.iter().enumerate() {
+            let y = FIRST_ROW + ROW_HEIGHT * i as i32;
+            let selected = i == self.cursor;
+            unsafe {
+                *DRAW_COLORS = if selected { 0x03 } else { 0x04 };
+            }
+            let label = item.label();
+            text(label, 80 - (label.len() as i32 * 4), y);
+            let value = item.value(settings);
+            text(&value, 80 - (value.len() as i32 * 4), y + 10);
+            if selected {
+                text("<", 16, y + 10);
+                text(">", 136, y + 10);
+            }
+        }
+        unsafe {
+            *DRAW_COLORS = 0x04;
+        }
+        text("X to start", 40, 150);
+        false
+    }
+}
@@ -0,0 +1,68 @@
+// Whole-arena map in the corner of the 2D view, for arenas too big to fit
+use crate::camera::Camera;
+use crate::dither::{owner_colours, owner_pixel};
+use crate::game::{Game, ARENA_SIZE, VIEW_SIZE};
+use crate::viewport::Viewport;
+use crate::wasm4::*;
+
+// Board cells per minimap pixel, each way
+const SCALE: usize = 2;
+const MARGIN: usize = 2;
+
+pub fn draw_minimap(game: &Game, camera: &Camera) {
+    let size = game.size.div_ceil(SCALE);
+    let x0 = SCREEN_SIZE as usize - MARGIN - size;
+    let y0 = SCREEN_SIZE as usize - MARGIN - size;
+    unsafe {
+        // Black with a grey border
+        *DRAW_COLORS = 0x14;
+    }
+    rect(
+        x0 as i32 - 1,
+        y0 as i32 - 1,
+        size as u32 + 2,
+        size as u32 + 2,
+    );
+
+    for y in 0..size {
+        for x in 0..size {
+            // Trails win over walls so they show up at this scale
+            let mut owner = None;
+            for r in y * SCALE..(y * SCALE + SCALE).min(game.size) {
+                for c in x * SCALE..(x * SCALE + SCALE).min(game.size) {
+                    owner = match (owner, game.board[r * ARENA_SIZE + c]) {
+                        (Some(old), Some(0)) => Some(old),
+                        (_, Some(new)) => Some(new),
+                        (old, None) => old,
+                    };
+                }
+            }
+            if let Some(owner) = owner {
+                Viewport::FULL.pixel(x0 + x, y0 + y, owner_pixel(owner, x, y));
+            }
+        }
+    }
+
+    // What's on screen
+    unsafe {
+        *DRAW_COLORS = 0x10;
+    }
+    rect(
+        (x0 + camera.col / SCALE) as i32,
+        (y0 + camera.row / SCALE) as i32,
+        (VIEW_SIZE / SCALE) as u32,
+        (VIEW_SIZE / SCALE) as u32,
+    );
+
+    for p in game.players.iter().filter(|p| p.alive) {
+        unsafe {
+            *DRAW_COLORS = 0x10 | (owner_colours(p.index).0 as u16 + 1);
+        }
+        rect(
+            (x0 + p.position.1 / SCALE) as i32 - 1,
+            (y0 + p.position.0 / SCALE) as i32 - 1,
+            3,
+            3,
+        );
+    }
+}
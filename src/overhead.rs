@@ -0,0 +1,91 @@
+// Top-down view for half of a split screen: the board at two pixels per
+// cell, centred on the player as far as the arena edges allow, so small
+// arenas fit whole.
+use crate::dither::{owner_colours, owner_pixel};
+use crate::facing_flags;
+use crate::game::{Direction, Game, ARENA_SIZE};
+use crate::sprites::{ARROW, BIKE_SIZE};
+use crate::viewport::Viewport;
+use crate::wasm4::*;
+
+const CELL: usize = 2;
+
+pub fn draw_overhead(game: &Game, viewer: usize, viewport: Viewport, arrows: bool) {
+    let pos = game.players[viewer].position;
+    let origin = (
+        origin(pos.0, game.size, viewport.height / CELL),
+        origin(pos.1, game.size, viewport.width / CELL),
+    );
+    for y in 0..viewport.height {
+        for x in 0..viewport.width {
+            let (r, c) = (origin.0 + (y / CELL) as i32, origin.1 + (x / CELL) as i32);
+            let inside = (0..game.size as i32).contains(&r) && (0..game.size as i32).contains(&c);
+            let colour = match inside.then(|| game.board[r as usize * ARENA_SIZE + c as usize]) {
+                Some(Some(owner)) => owner_pixel(owner, x, y),
+                _ => 3,
+            };
+            viewport.pixel(x, y, colour);
+        }
+    }
+
+    // Heads with a grey outline, like the minimap's, and a nub on the front
+    // side for the heading. Countdown arrows go a gap beyond the nub.
+    for p in game.players.iter().filter(|p| p.alive) {
+        let x = (p.position.1 as i32 - origin.1) * CELL as i32;
+        let y = (p.position.0 as i32 - origin.0) * CELL as i32;
+        let (dy, dx) = step(p.direction);
+        unsafe {
+            *DRAW_COLORS = 0x10 | (owner_colours(p.index).0 as u16 + 1);
+        }
+        if fits(viewport, x - 1, y - 1, 4) {
+            rect(viewport.x as i32 + x - 1, viewport.y as i32 + y - 1, 4, 4);
+        }
+        if fits(viewport, x + 3 * dx, y + 3 * dy, 2) {
+            rect(
+                viewport.x as i32 + x + 3 * dx,
+                viewport.y as i32 + y + 3 * dy,
+                2,
+                2,
+            );
+        }
+        let (ax, ay) = (x - 1 + 7 * dx, y - 1 + 7 * dy);
+        if arrows && fits(viewport, ax, ay, BIKE_SIZE as i32) {
+            unsafe {
+                *DRAW_COLORS = (owner_colours(p.index).0 as u16 + 1) << 4;
+            }
+            blit(
+                &ARROW,
+                viewport.x as i32 + ax,
+                viewport.y as i32 + ay,
+                BIKE_SIZE,
+                BIKE_SIZE,
+                BLIT_2BPP | facing_flags(p.direction),
+            );
+        }
+    }
+}
+
+// Whether a square of `size` pixels at (x, y) lies inside the viewport
+fn fits(viewport: Viewport, x: i32, y: i32, size: i32) -> bool {
+    x >= 0 && y >= 0 && x + size <= viewport.width as i32 && y + size <= viewport.height as i32
+}
+
+// One cell forward as (row, col) deltas
+fn step(dir: Direction) -> (i32, i32) {
+    match dir {
+        Direction::North => (-1, 0),
+        Direction::South => (1, 0),
+        Direction::East => (0, 1),
+        Direction::West => (0, -1),
+    }
+}
+
+// First cell shown along one axis: centred on `pos` when the arena is
+// bigger than the view, otherwise the arena itself centred
+fn origin(pos: usize, size: usize, cells: usize) -> i32 {
+    if size <= cells {
+        -(((cells - size) / 2) as i32)
+    } else {
+        pos.saturating_sub(cells / 2).min(size - cells) as i32
+    }
+}
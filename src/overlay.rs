@@ -0,0 +1,145 @@
+// AI debug overlay, only built with the `ai-debug` feature
+use crate::ai::Trace;
+use crate::camera::Camera;
+use crate::game::{Game, ARENA_CELLS, ARENA_SIZE};
+use crate::wasm4::*;
+use crate::{draw_tile, tile_byte};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Off,
+    Flood,
+    Voronoi,
+    Scores,
+}
+
+pub struct Overlay {
+    mode: Mode,
+    subject: usize,
+}
+
+impl Overlay {
+    pub const fn new() -> Self {
+        Overlay {
+            mode: Mode::Off,
+            subject: 1,
+        }
+    }
+
+    /// Z cycles what's shown, Down cycles which player it's shown for
+    pub fn input(&mut self, game: &Game, just_pressed: u8) {
+        if just_pressed & BUTTON_2 != 0 {
+            self.mode = match self.mode {
+                Mode::Off => Mode::Flood,
+                Mode::Flood => Mode::Voronoi,
+                Mode::Voronoi => Mode::Scores,
+                Mode::Scores => Mode::Off,
+            };
+        } else if just_pressed & BUTTON_DOWN != 0 {
+            self.subject = (self.subject + 1) % game.players.len();
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.mode != Mode::Off
+    }
+
+    pub fn subject(&self) -> usize {
+        self.subject
+    }
+
+    /// Draws over the 2D board. `trace` is the subject's last decision, if
+    /// it's an AI.
+    pub fn draw(&self, game: &Game, camera: &Camera, trace: Option<&Trace>) {
+        let subject = &game.players[self.subject];
+        match self.mode {
+            Mode::Off => {}
+            Mode::Flood => {
+                game.flood(&[subject.position], &[], ARENA_CELLS, |ix, dist, _| {
+                    if dist != 0 {
+                        dot(camera, ix, subject.index);
+                    }
+                });
+            }
+            Mode::Voronoi => {
+                let mut heads = [(0, 0); 2];
+                let mut owners = [0; 2];
+                let mut count = 0;
+                for p in game.players.iter().filter(|p| p.alive) {
+                    heads[count] = p.position;
+                    owners[count] = p.index;
+                    count += 1;
+                }
+                game.flood(&heads[..count], &[], ARENA_CELLS, |ix, dist, nearest| {
+                    // Skip the bikes' own heads. Equidistant cells are
+                    // nobody's, shown in wall colour.
+                    if dist != 0 {
+                        let owner = match nearest.count_ones() {
+                            1 => owners[nearest.trailing_zeros() as usize],
+                            _ => 0,
+                        };
+                        dot(camera, ix, owner);
+                    }
+                });
+            }
+            Mode::Scores => {
+                let Some(trace) = trace else {
+                    text_line("not an AI", 0);
+                    return;
+                };
+                for (dir, _) in &trace.scores {
+                    let (r, c) = dir.next_position(subject.position);
+                    dot(camera, r * ARENA_SIZE + c, subject.index);
+                }
+                let chosen = trace.chosen.map(|d| d.next_position(subject.position));
+                if let Some(pos) = chosen.and_then(|pos| camera.view_pos(pos)) {
+                    draw_tile(pos, Some(subject.index));
+                }
+                for (line, (dir, score)) in trace.scores.iter().enumerate() {
+                    text_line(
+                        &format!("{} {}", &format!("{:?}", dir)[..1], score),
+                        line + 1,
+                    );
+                }
+                text_line(trace.reason, 0);
+            }
+        }
+    }
+
+    /// Logs the subject's decision for this tick to the debug console
+    pub fn log(&self, game: &Game, player: usize, trace: &Trace) {
+        if self.mode == Mode::Off || player != self.subject {
+            return;
+        }
+        let mut line = format!("t{} P{} {}", game.ticks, player + 1, trace.reason);
+        for (dir, score) in &trace.scores {
+            line += &format!(" {:?}={}", dir, score);
+        }
+        if let Some(chosen) = trace.chosen {
+            line += &format!(" -> {:?}", chosen);
+        }
+        crate::wasm4::trace(line);
+    }
+}
+
+// Colours the middle 2x2 pixels of a tile, if it's in view
+fn dot(camera: &Camera, ix: usize, c: u8) {
+    let Some(pos) = camera.view_pos((ix / ARENA_SIZE, ix % ARENA_SIZE)) else {
+        return;
+    };
+    let top = tile_byte(pos);
+    unsafe {
+        for r in 1..3 {
+            let byte = &mut (*FRAMEBUFFER)[top + r * 40];
+            *byte = (*byte & !0b0011_1100) | (c << 4) | (c << 2);
+        }
+    }
+}
+
+// Text counted up from the bottom of the screen
+fn text_line(s: &str, line: usize) {
+    unsafe {
+        *DRAW_COLORS = 0x41;
+    }
+    text(s, 0, 152 - 8 * line as i32);
+}
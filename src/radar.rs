@@ -0,0 +1,124 @@
+// Situational awareness for the first-person view: a round map of the
+// board around the bike, turned so its heading points up, and markers at
+// the edge of the view pointing at each opponent.
+use crate::dither::{owner_colours, owner_pixel};
+use crate::game::{Game, ARENA_SIZE};
+use crate::trig::{atan2, cos, degrees, sin, ANGLES, ONE};
+use crate::viewport::Viewport;
+use crate::wasm4::*;
+
+// Radius in pixels, one board cell per pixel
+const RADIUS: i32 = 15;
+const MARGIN: i32 = 2;
+
+/// Draws over a first-person view of bike `viewer` looking `facing`, with
+/// the same `fov` in degrees
+pub fn draw_radar(game: &Game, viewer: usize, facing: i32, fov: u8, viewport: Viewport) {
+    let me = game.players[viewer];
+    let eye = (
+        me.position.1 as i32 * ONE + ONE / 2,
+        me.position.0 as i32 * ONE + ONE / 2,
+    );
+    // Board y grows downwards, right is a quarter turn clockwise of forward
+    let forward = (cos(facing), -sin(facing));
+    let right = (-forward.1, forward.0);
+    let centre = (
+        viewport.width as i32 - MARGIN - RADIUS - 1,
+        viewport.height as i32 - MARGIN - RADIUS - 1,
+    );
+
+    for sy in -RADIUS..=RADIUS {
+        for sx in -RADIUS..=RADIUS {
+            let d = sx * sx + sy * sy;
+            if d > RADIUS * RADIUS {
+                continue;
+            }
+            let (x, y) = ((centre.0 + sx) as usize, (centre.1 + sy) as usize);
+            // Grey rim
+            if d > (RADIUS - 1) * (RADIUS - 1) {
+                viewport.pixel(x, y, 0);
+                continue;
+            }
+            let r = (eye.1 + right.1 * sx - forward.1 * sy).div_euclid(ONE);
+            let c = (eye.0 + right.0 * sx - forward.0 * sy).div_euclid(ONE);
+            let inside = (0..game.size as i32).contains(&r) && (0..game.size as i32).contains(&c);
+            let colour = match inside.then(|| game.board[r as usize * ARENA_SIZE + c as usize]) {
+                Some(Some(owner)) => owner_pixel(owner, x, y),
+                _ => 3,
+            };
+            viewport.pixel(x, y, colour);
+        }
+    }
+    // The bike itself in the middle, pointing up
+    let own = owner_colours(me.index).0;
+    viewport.pixel(centre.0 as usize, centre.1 as usize, own);
+    viewport.pixel(centre.0 as usize, centre.1 as usize - 1, own);
+
+    for p in game
+        .players
+        .iter()
+        .filter(|p| p.alive && p.index != me.index)
+    {
+        let colour = owner_colours(p.index).0;
+        let rel = (
+            p.position.1 as i32 - me.position.1 as i32,
+            p.position.0 as i32 - me.position.0 as i32,
+        );
+        // Turned into radar pixels, pulled in to the rim if too far
+        let mut sx = (rel.0 * right.0 + rel.1 * right.1) / ONE;
+        let mut sy = -(rel.0 * forward.0 + rel.1 * forward.1) / ONE;
+        let d = sx * sx + sy * sy;
+        let inner = RADIUS - 3;
+        if d > inner * inner {
+            let len = d.isqrt();
+            sx = sx * inner / len;
+            sy = sy * inner / len;
+        }
+        mark(viewport, centre.0 + sx, centre.1 + sy, colour);
+
+        // Edge marker: along the top when it's in front, otherwise on the
+        // side to turn towards
+        let off = (atan2(-rel.1, rel.0) - facing + ANGLES / 2).rem_euclid(ANGLES) - ANGLES / 2;
+        let fov = degrees(fov as i32);
+        let (width, height) = (viewport.width as i32, viewport.height as i32);
+        unsafe {
+            *DRAW_COLORS = colour as u16 + 1;
+        }
+        if off.abs() <= fov / 2 {
+            let col = (fov / 2 - off) * width / fov;
+            arrow_down(viewport, col.clamp(2, width - 3), 1);
+        } else {
+            arrow_side(viewport, off > 0, height / 2);
+        }
+    }
+}
+
+// 2x2 dot at radar pixel (x, y)
+fn mark(viewport: Viewport, x: i32, y: i32, colour: u8) {
+    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        viewport.pixel((x + dx) as usize, (y + dy) as usize, colour);
+    }
+}
+
+// Small triangle pointing down, its tip at (x, y + 2)
+fn arrow_down(viewport: Viewport, x: i32, y: i32) {
+    let (vx, vy) = (viewport.x as i32, viewport.y as i32);
+    for i in 0..3 {
+        rect(vx + x - 2 + i, vy + y + i, 5 - 2 * i as u32, 1);
+    }
+}
+
+// Small triangle against the left or right edge, pointing out of the view
+fn arrow_side(viewport: Viewport, left: bool, y: i32) {
+    let (vx, vy) = (viewport.x as i32, viewport.y as i32);
+    let edge = if left {
+        vx + 1
+    } else {
+        vx + viewport.width as i32 - 2
+    };
+    // Tip at the edge
+    for i in 0..3 {
+        let x = if left { edge + i } else { edge - i };
+        rect(x, vy + y - i, 1, 1 + 2 * i as u32);
+    }
+}
@@ -1,20 +1,62 @@
-use crate::wasm4::FRAMEBUFFER;
-use crate::Direction;
-use crate::BOARD;
-// 160x160 pixels projection plane - 160 columns
-// 60 degrees = pi/6 rad FOV
-// angle_num diff per column (ray) = FOV / 160
-// Centre of projection plane = (80,80)
+use crate::dither::{lit, owner_pixel, LEVELS};
+use crate::game::{Direction, Game, Player, ARENA_SIZE};
+use crate::sprites::{BIKE_PROFILE, BIKE_PROFILE_WIDTH};
+use crate::trig::{atan2, cos, degrees, sin, ANGLES, ONE};
+use crate::viewport::Viewport;
+// The projection plane is the viewport, one ray per column
+// Each ray goes through its column on the flat plane, so they're spaced
+// closer together towards the edges
+// Centre of projection plane = middle of the viewport
 //
-// Walls are 4x128x4
-// Camera height =65
-// This code is FOV independent, but trig lookup tables are not!
-const FOV: usize = 30; // degrees
-const ANGLE_DIFF_DEGREES: f64 = FOV as f64 / 160.0;
-const WALL_HEIGHT: usize = 8;
-const WALL_SIZE: usize = 4; // x,z
-const PROJECTION_DISTANCE: usize = 138; // Approximation of 80 half-width / tan(pi/6) i.e. 2**7
-const SLICE_HEIGHT_CONST: usize = PROJECTION_DISTANCE * WALL_HEIGHT; // Divide this by distance to get actual height
+// Rays step through the board grid one cell boundary at a time (DDA), so
+// every ray finds the first occupied cell it passes through whatever its
+// angle, including rays running along grid lines. All of it is fixed point,
+// see trig.rs for the angle units.
+//
+// Walls are sized by their distance along the view direction rather than
+// along the ray, otherwise they bulge towards the middle of the screen.
+//
+// From first-person the eye is half way up the walls, so the first wall a
+// ray meets hides everything behind it. The chase camera is higher than the
+// walls and looks over them, so its rays carry on past each wall, drawing
+// its top and then whatever further walls show above it. Bikes are drawn
+// afterwards as flat sprites facing the camera, hidden below the row each
+// column was covered down to by walls nearer than them.
+const WALL_HEIGHT: i32 = 8;
+const WALL_SIZE: i32 = 4; // x,z
+const BIKE_HEIGHT: i32 = 4;
+
+// Chase camera: tiles behind the bike, eye height in the same units as
+// WALL_HEIGHT, and the narrowest field of view that keeps the bike in shot
+const CHASE_DISTANCE: i32 = 4;
+const CHASE_HEIGHT: i32 = 12;
+const CHASE_FOV_MIN: u8 = 60;
+// Most walls a column keeps track of, far ones past this are dropped
+const MAX_SPANS: usize = 24;
+
+// Frames the camera takes to swing round a quarter turn
+const TURN_FRAMES: i32 = 6;
+
+// Field of view choices in degrees
+pub const FOV_MIN: u8 = 30;
+pub const FOV_MAX: u8 = 90;
+pub const FOV_STEP: u8 = 10;
+pub const FOV_DEFAULT: u8 = 60;
+
+// Wall shading, in dither levels out of LEVELS. Walls fade towards the
+// floor colour with distance, and faces seen along the y axis are darker
+// so corners stand out.
+const FADE_DISTANCE: i32 = 32; // tiles to fade out completely
+const MIN_LEVEL: i32 = 3;
+const SIDE_SHADE: i32 = 4;
+
+// Floor grid and sky. Grid lines are GRID_LINE wide, scaled by ONE, and
+// fade out by GRID_FADE_DISTANCE tiles. The sky is a band SKY_BAND pixels
+// tall above the horizon of solid lines, spreading out as they go up. Walls
+// are dithered and never drawn in lines, so far ones don't melt into it.
+const GRID_LINE: i64 = ONE as i64 / 12;
+const GRID_FADE_DISTANCE: i32 = 24;
+const SKY_BAND: i32 = 24;
 
 #[derive(Copy, Clone)]
 enum IntersectionKind {
@@ -24,249 +66,532 @@ enum IntersectionKind {
 
 struct Intersection {
     kind: IntersectionKind,
-    distance: f64,
+    // Where the ray enters and leaves the cell, in tiles, scaled by ONE
+    distance: i32,
+    exit: i32,
     colour: u8,
 }
 
-fn calculate_distance(px: usize, py: usize, ax: usize, ay: usize, angle_num: usize) -> f64 {
-    // let mut dist: f64;
-    // if py != ay && sin(angle_num) != 0.0 {
-    //     dist = py as f64 - ay as f64;
-    //     if dist < 0.0 {
-    //         dist *= -1.0;
-    //     };
-    //     dist /= sin(angle_num);
-    // } else {
-    //     dist = px as f64 - ax as f64;
-    //     if dist < 0.0 {
-    //         dist *= -1.0;
-    //     };
-    //     dist /= cos(angle_num);
-    // }
-    // dist
-    ((px as f64 - ax as f64).powi(2) + (py as f64 - ay as f64).powi(2)).sqrt()
+/// Where the 3D view is seen from
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Pov {
+    FirstPerson,
+    // Behind and above the bike
+    Chase,
 }
 
-fn draw_cols<I>(angle_nums: I, grid_origin: (usize, usize), dir: Direction)
-where
-    I: Iterator<Item = usize>,
-{
-    for (col, angle_num) in angle_nums.enumerate() {
-        let intersection = find_intersection(grid_origin, angle_num, dir);
-        let height = (SLICE_HEIGHT_CONST as f64 / intersection.distance).floor() as usize;
-        for row in 0..160 {
-            let byte: usize = (40 * row) + (col / 4);
-            let bit: u8 = col as u8 % 4;
-            let bitshift: u8 = 2 * (3 - bit);
-            let target_col: u8 = if (row as i32) > (80 - (height as i32 / 2))
-                && (row as i32) < (80 + (height as i32 / 2))
-            {
-                // match intersection.kind {
-                //     IntersectionKind::VerticalGrid => 1,
-                //     IntersectionKind::HorizontalGrid => 2,
-                // }
-                intersection.colour
-            } else {
-                3
-            };
-            unsafe {
-                (*FRAMEBUFFER)[byte] =
-                    ((*FRAMEBUFFER)[byte] & (!((3 << bitshift) as u8))) | (target_col << bitshift);
-            }
+impl Pov {
+    /// Field of view actually used given the `fov` setting, in degrees
+    pub fn fov(self, fov: u8) -> u8 {
+        match self {
+            Pov::FirstPerson => fov,
+            Pov::Chase => fov.max(CHASE_FOV_MIN),
         }
     }
 }
 
-pub fn draw_3d(grid_origin: (usize, usize), dir: Direction) {
-    use Direction::*;
-    // FOV independent - depends on num columns
-    match dir {
-        North => {
-            let angles = (((90 - FOV / 2) as f64 / ANGLE_DIFF_DEGREES) as usize
-                ..((90 + FOV / 2) as f64 / ANGLE_DIFF_DEGREES) as usize)
-                .rev();
-            draw_cols(angles, grid_origin, dir);
-        }
-        South => {
-            let angles = (((270 - FOV / 2) as f64 / ANGLE_DIFF_DEGREES) as usize
-                ..((270 + FOV / 2) as f64 / ANGLE_DIFF_DEGREES) as usize)
-                .rev();
-            draw_cols(angles, grid_origin, dir);
-        }
-        East => {
-            let angles = (0..((FOV / 2) as f64 / ANGLE_DIFF_DEGREES) as usize)
-                .rev()
-                .chain(
-                    (((360 - (FOV / 2)) as f64 / ANGLE_DIFF_DEGREES) as usize
-                        ..(360.0 / ANGLE_DIFF_DEGREES) as usize)
-                        .rev(),
-                );
-            draw_cols(angles, grid_origin, dir);
+// Rows of a column covered by one wall face or top
+#[derive(Clone, Copy)]
+struct Span {
+    top: i32,
+    bottom: i32,
+    colour: u8,
+    level: u8,
+}
+
+const NO_SPAN: Span = Span {
+    top: 0,
+    bottom: 0,
+    colour: 0,
+    level: 0,
+};
+
+// Where the view is from, all in fixed point
+struct View {
+    // Camera position in tiles, scaled by ONE
+    eye: (i32, i32),
+    // Angle, see trig.rs
+    facing: i32,
+    // Distance to the projection plane in pixels, half width / tan(fov / 2)
+    projection: i32,
+    viewport: Viewport,
+    // Row of the horizon within the viewport
+    horizon: i32,
+    // In the same units as WALL_HEIGHT
+    eye_height: i32,
+}
+
+impl View {
+    // Pixels on screen that `units` of height make `distance` away
+    fn rows(&self, units: i32, distance: i32) -> i32 {
+        units * self.projection * ONE / (distance * WALL_SIZE).max(1)
+    }
+}
+
+// Draws walls, floor and sky. Returns, for each bike `ahead` of the camera,
+// the first row in each column hidden by walls nearer than it. There's
+// room for a full screen's width of columns.
+fn draw_cols(game: &Game, view: &View, ahead: [i32; 2]) -> [[i32; 160]; 2] {
+    let (width, height) = (view.viewport.width, view.viewport.height);
+    let mut hidden = [[height as i32; 160]; 2];
+    // Leftmost column looks furthest anticlockwise of the heading
+    let half = width as i32 / 2;
+    for col in 0..width {
+        let angle = view.facing + atan2(half - col as i32, view.projection);
+        let slant = cos(angle - view.facing);
+        let mut spans = [NO_SPAN; MAX_SPANS];
+        let mut count = 0;
+        // Rows from here down are covered by nearer walls
+        let mut limit = height as i32;
+        let mut walk = Walk::new(view.eye, angle);
+        while limit > view.horizon {
+            let Some(i) = walk.next(game) else {
+                break;
+            };
+            let near = (i.distance as i64 * slant as i64 / ONE as i64) as i32;
+            let far = (i.exit as i64 * slant as i64 / ONE as i64) as i32;
+            let top = view.horizon - view.rows(WALL_HEIGHT - view.eye_height, near);
+            let bottom = view.horizon + view.rows(view.eye_height, near);
+            // The top only shows from above, running back to where the
+            // ray leaves the cell
+            let back = view.horizon - view.rows(WALL_HEIGHT - view.eye_height, far);
+            let face = Span {
+                top,
+                bottom: bottom.min(limit),
+                colour: i.colour,
+                level: shade(i.kind, near),
+            };
+            let lid = Span {
+                top: back,
+                bottom: top.min(limit),
+                colour: i.colour,
+                level: shade(IntersectionKind::VerticalGrid, near) / 2,
+            };
+            for span in [face, lid] {
+                if span.top < span.bottom && count < MAX_SPANS {
+                    spans[count] = span;
+                    count += 1;
+                }
+            }
+            limit = limit.min(top).min(back);
+            for (b, rows) in hidden.iter_mut().enumerate() {
+                if near < ahead[b] {
+                    rows[col] = limit;
+                }
+            }
         }
-        West => {
-            let angles = (((180 - FOV / 2) as f64 / ANGLE_DIFF_DEGREES) as usize
-                ..((180 + FOV / 2) as f64 / ANGLE_DIFF_DEGREES) as usize)
-                .rev();
-            draw_cols(angles, grid_origin, dir);
+        let ray = Ray {
+            eye: view.eye,
+            dx: cos(angle),
+            dy: -sin(angle),
+            slant,
+        };
+        for row in 0..height {
+            let r = row as i32;
+            let above = view.horizon - r;
+            let target_col: u8 = match spans[..count].iter().find(|s| s.top <= r && r < s.bottom) {
+                Some(s) if lit(s.level, col, row) => owner_pixel(s.colour, col, row),
+                Some(_) => 3,
+                None if above >= 0 => sky_pixel(above),
+                None => floor_pixel(&ray, view, col, row, -above),
+            };
+            view.viewport.pixel(col, row, target_col);
         }
     }
+    hidden
 }
 
-fn find_intersection(
-    grid_origin: (usize, usize),
-    angle_num: usize,
-    dir: Direction,
-) -> Intersection {
-    // e.g. for North heading will check all angle_nums in range:
-    // 90-(FOV/2) -> 90+(FOV/2)
-    // angle can be any multiple of FOV/160
-    // angle_num between 0 and 959
-    let h = find_horizontal_intersection(grid_origin, angle_num, dir);
-    let v = find_vertical_intersection(grid_origin, angle_num, dir);
-
-    match (h, v) {
-        (None, None) => unreachable!(),
-        (Some(hi), None) => hi,
-        (None, Some(vi)) => vi,
-        (Some(hi), Some(vi)) => {
-            if hi.distance <= vi.distance {
-                hi
+// Bikes in front of the camera, furthest first. `bikes` holds how far
+// each one is to the left of the view direction and ahead of the camera. The viewer's own bike,
+// only in front in the chase view, is always drawn in full.
+fn draw_bikes(
+    game: &Game,
+    viewer: usize,
+    view: &View,
+    bikes: [(i32, i32); 2],
+    hidden: &[[i32; 160]; 2],
+) {
+    let mut order = [0, 1];
+    order.sort_unstable_by_key(|&b| -bikes[b].1);
+    for b in order {
+        let (p, (left, ahead)) = (game.players[b], bikes[b]);
+        if !p.alive || ahead < ONE / 4 {
+            continue;
+        }
+        // Column of the middle of the bike, where it meets the projection
+        // plane like the rays do
+        let (width, height) = (view.viewport.width as i32, view.viewport.height as i32);
+        let centre = width / 2 - (view.projection as i64 * left as i64 / ahead as i64) as i32;
+        // One tile wide, standing on the floor
+        let sprite_width = view.projection * ONE / ahead;
+        let sprite_height = view.rows(BIKE_HEIGHT, ahead);
+        let bottom = view.horizon + view.rows(view.eye_height, ahead);
+        let level = shade(IntersectionKind::VerticalGrid, ahead);
+        let (left, top) = (centre - sprite_width / 2, bottom - sprite_height);
+        // Only the part that's in the viewport
+        for col in left.max(0)..(left + sprite_width).min(width) {
+            let u = ((col - left) * BIKE_PROFILE_WIDTH as i32 / sprite_width) as usize;
+            let visible = if b == viewer {
+                height
             } else {
-                vi
+                hidden[b][col as usize]
+            };
+            for row in top.max(0)..bottom.min(visible) {
+                let v = ((row - top) * BIKE_PROFILE.len() as i32 / sprite_height) as usize;
+                let (col, row) = (col as usize, row as usize);
+                if BIKE_PROFILE[v] & (0x80 >> u) != 0 && lit(level, col, row) {
+                    view.viewport
+                        .pixel(col, row, owner_pixel(p.index, col, row));
+                }
             }
         }
     }
 }
 
-fn find_horizontal_intersection(
-    grid_origin: (usize, usize),
-    angle_num: usize,
-    dir: Direction,
-) -> Option<Intersection> {
-    // Intersections with horizontal grid-lines, y-direction
-    // Origin is  middle of block\
-    use Direction::*;
-    let py = match dir {
-        North => grid_origin.0 * WALL_SIZE,
-        South => grid_origin.0 * WALL_SIZE + WALL_SIZE,
-        _ => (grid_origin.0 * WALL_SIZE) + (WALL_SIZE / 2),
-    };
-    let px = match dir {
-        East => (grid_origin.1 * WALL_SIZE) + WALL_SIZE,
-        West => (grid_origin.1 * WALL_SIZE),
-        _ => (grid_origin.1 * WALL_SIZE) + (WALL_SIZE / 2),
-    };
-    let angle: f64 = ANGLE_DIFF_DEGREES * angle_num as f64;
+// One column's ray, for finding where it meets the floor
+struct Ray {
+    // Camera position in tiles, scaled by ONE
+    eye: (i32, i32),
+    dx: i32,
+    dy: i32,
+    // cos of the angle between the ray and the view direction
+    slant: i32,
+}
 
-    let mut ay = if angle > 180.0 {
-        grid_origin.0 * WALL_SIZE + WALL_SIZE
+// A glow just above the horizon: lines 0, 1, 3, 6, 10... rows up, where
+// 8 * above + 1 is a square
+fn sky_pixel(above: i32) -> u8 {
+    let k = (8 * above + 1).isqrt();
+    if above < SKY_BAND && k * k == 8 * above + 1 {
+        0
     } else {
-        grid_origin.0 * WALL_SIZE - 1
+        3
+    }
+}
+
+// Grid lines along the tile edges, fading out into the distance
+fn floor_pixel(ray: &Ray, view: &View, col: usize, row: usize, below: i32) -> u8 {
+    // Distance along the view direction to where this row meets the floor
+    let below = below as i64;
+    let ahead =
+        view.eye_height as i64 * view.projection as i64 * ONE as i64 / (below * WALL_SIZE as i64);
+    let level = LEVELS as i64 - LEVELS as i64 * ahead / (GRID_FADE_DISTANCE * ONE) as i64;
+    if level <= 0 || !lit(level as u8, col, row) {
+        return 3;
+    }
+    let along = ahead * ONE as i64 / ray.slant.max(1) as i64;
+    let x = ray.eye.0 as i64 + ray.dx as i64 * along / ONE as i64;
+    let y = ray.eye.1 as i64 + ray.dy as i64 * along / ONE as i64;
+    let on_line = |v: i64| (v + GRID_LINE / 2).rem_euclid(ONE as i64) < GRID_LINE;
+    if on_line(x) || on_line(y) {
+        0
+    } else {
+        3
+    }
+}
+
+// Dither level of a wall face `distance` tiles away, scaled by ONE
+fn shade(kind: IntersectionKind, distance: i32) -> u8 {
+    let mut level = LEVELS as i32 - LEVELS as i32 * distance / (FADE_DISTANCE * ONE);
+    if let IntersectionKind::HorizontalGrid = kind {
+        level -= SIDE_SHADE;
+    }
+    level.max(MIN_LEVEL) as u8
+}
+
+/// 3D view for bike `viewer` from `pov`, looking `facing` (see
+/// ViewAngle). `progress` is how far through the current tick it is, out
+/// of ONE, to glide the bikes between tiles. `fov` is in degrees across
+/// the viewport's width.
+pub fn draw_3d(
+    game: &Game,
+    viewer: usize,
+    pov: Pov,
+    facing: i32,
+    progress: i32,
+    fov: u8,
+    viewport: Viewport,
+) {
+    let (dx, dy) = (cos(facing), -sin(facing));
+    let (x, y) = bike_position(game, &game.players[viewer], progress);
+    let (eye, eye_height, horizon) = match pov {
+        Pov::FirstPerson => ((x, y), WALL_HEIGHT / 2, viewport.height as i32 / 2),
+        // Pulled back along the view, kept on the board, and tilted down
+        // by moving the horizon up
+        Pov::Chase => {
+            let edge = ARENA_SIZE as i32 * ONE - ONE / 2;
+            (
+                (
+                    (x - dx * CHASE_DISTANCE).clamp(ONE / 2, edge),
+                    (y - dy * CHASE_DISTANCE).clamp(ONE / 2, edge),
+                ),
+                CHASE_HEIGHT,
+                viewport.height as i32 / 4,
+            )
+        }
     };
+    let fov = degrees(pov.fov(fov) as i32);
+    let half = viewport.width as i32 / 2;
+    let view = View {
+        eye,
+        facing,
+        projection: half * cos(fov / 2) / sin(fov / 2),
+        viewport,
+        horizon,
+        eye_height,
+    };
+    let bikes = game.players.map(|p| {
+        let at = bike_position(game, &p, progress);
+        let (x, y) = (at.0 - view.eye.0, at.1 - view.eye.1);
+        // Distances across and along the view direction
+        let left = ((x as i64 * dy as i64 - y as i64 * dx as i64) / ONE as i64) as i32;
+        let ahead = ((x as i64 * dx as i64 + y as i64 * dy as i64) / ONE as i64) as i32;
+        (left, ahead)
+    });
+    let hidden = draw_cols(game, &view, bikes.map(|b| b.1));
+    draw_bikes(game, viewer, &view, bikes, &hidden);
+}
+
+// Where a bike is drawn, scaled by ONE, partway along from the tile it came
+// from to the tile it's on
+fn bike_position(game: &Game, p: &Player, progress: i32) -> (i32, i32) {
+    use Direction::*;
+    let (x, y) = (
+        p.position.1 as i32 * ONE + ONE / 2,
+        p.position.0 as i32 * ONE + ONE / 2,
+    );
+    // A head links back to the tile it came from, and nothing else yet
+    let links = game.links[p.position.0 * ARENA_SIZE + p.position.1];
+    let back = ONE - progress;
+    match [North, South, East, West]
+        .into_iter()
+        .find(|d| links & d.link() != 0)
+    {
+        Some(North) => (x, y - back),
+        Some(South) => (x, y + back),
+        Some(East) => (x + back, y),
+        Some(West) => (x - back, y),
+        None => (x, y),
+    }
+}
 
-    if angle == 0.0 || angle == 180.0 {
-        return None;
+/// Camera heading for the 3D views, swinging round over a few
+/// frames rather than snapping when the bike turns
+pub struct ViewAngle {
+    angle: i32,
+}
+
+impl ViewAngle {
+    pub const fn new() -> Self {
+        ViewAngle { angle: 0 }
     }
 
-    let ax_neg = (px as f64
-        + ((py as i32 - ay as i32) as f64 / f64::tan(angle / 180.0 * std::f64::consts::PI)))
-    .floor();
-    if ax_neg < 0.0 {
-        return None;
+    /// Current heading in trig.rs angle units
+    pub fn angle(&self) -> i32 {
+        self.angle
     }
-    let mut ax: usize = ax_neg as usize;
-    let xa: i32 =
-        (WALL_SIZE as f64 / f64::tan(angle / 180.0 * std::f64::consts::PI)).floor() as i32;
 
-    while ay >= 0 && ay < 160 && ax >= 0 && ax < 160 {
-        let gridx = ax / WALL_SIZE;
-        let gridy = ay / WALL_SIZE;
+    pub fn snap(&mut self, dir: Direction) {
+        self.angle = heading(dir);
+    }
 
-        unsafe {
-            if let Some(c) = BOARD[40 * gridy + gridx] {
-                let dist = calculate_distance(px, py, ax, ay, angle_num);
+    /// Turns one frame's worth towards `dir`
+    pub fn update(&mut self, dir: Direction) {
+        let speed = ANGLES / 4 / TURN_FRAMES;
+        // Shortest way round
+        let diff = (heading(dir) - self.angle + ANGLES / 2).rem_euclid(ANGLES) - ANGLES / 2;
+        if diff.abs() <= speed {
+            self.angle = heading(dir);
+        } else {
+            self.angle = (self.angle + speed * diff.signum()).rem_euclid(ANGLES);
+        }
+    }
+}
+
+// Angle anticlockwise from east
+fn heading(dir: Direction) -> i32 {
+    use Direction::*;
+    match dir {
+        East => 0,
+        North => ANGLES / 4,
+        West => ANGLES / 2,
+        South => ANGLES * 3 / 4,
+    }
+}
+
+// A ray stepping through the board one cell boundary at a time
+struct Walk {
+    grid: (i32, i32),
+    step: (i32, i32),
+    // Ray length between successive vertical / horizontal grid lines, and
+    // to the next one of each, scaled by ONE. A ray parallel to one set of
+    // lines never crosses them.
+    delta: (i32, i32),
+    side: (i32, i32),
+}
+
+impl Walk {
+    // From `eye`, in tiles scaled by ONE
+    fn new(eye: (i32, i32), angle: i32) -> Self {
+        // Board y grows downwards
+        let (dx, dy) = (cos(angle), -sin(angle));
+        let delta = (delta(dx), delta(dy));
+        Walk {
+            grid: (eye.0 / ONE, eye.1 / ONE),
+            step: (dx.signum(), dy.signum()),
+            delta,
+            side: (
+                first_side(eye.0, dx, delta.0),
+                first_side(eye.1, dy, delta.1),
+            ),
+        }
+    }
+
+    /// Carries on to the next occupied cell. None once the ray leaves the
+    /// board.
+    fn next(&mut self, game: &Game) -> Option<Intersection> {
+        loop {
+            let (kind, distance) = if self.side.0 < self.side.1 {
+                self.grid.0 += self.step.0;
+                self.side.0 += self.delta.0;
+                (IntersectionKind::VerticalGrid, self.side.0 - self.delta.0)
+            } else {
+                self.grid.1 += self.step.1;
+                self.side.1 += self.delta.1;
+                (IntersectionKind::HorizontalGrid, self.side.1 - self.delta.1)
+            };
+            let (x, y) = self.grid;
+            if !(0..ARENA_SIZE as i32).contains(&x) || !(0..ARENA_SIZE as i32).contains(&y) {
+                return None;
+            }
+            if let Some(c) = game.board[ARENA_SIZE * y as usize + x as usize] {
                 return Some(Intersection {
-                    kind: IntersectionKind::HorizontalGrid,
-                    distance: dist,
+                    kind,
+                    distance,
+                    exit: self.side.0.min(self.side.1),
                     colour: c,
                 });
             }
         }
-
-        let ydiff: i32 = if angle > 180.0 {
-            WALL_SIZE as i32
-        } else {
-            -(WALL_SIZE as i32)
-        }; // Ya
-        ay = (ay as i32 + ydiff) as usize;
-        ax = (ax as i32 + xa) as usize;
     }
-
-    None
 }
 
-fn find_vertical_intersection(
-    grid_origin: (usize, usize),
-    angle_num: usize,
-    dir: Direction,
-) -> Option<Intersection> {
-    // Intersections with vertical grid-lines, x-direction
-    // Origin is  middle of block
-    use Direction::*;
-    let py = match dir {
-        North => grid_origin.0 * WALL_SIZE,
-        South => grid_origin.0 * WALL_SIZE + WALL_SIZE,
-        _ => (grid_origin.0 * WALL_SIZE) + (WALL_SIZE / 2),
-    };
-    let px = match dir {
-        East => (grid_origin.1 * WALL_SIZE) + WALL_SIZE,
-        West => (grid_origin.1 * WALL_SIZE),
-        _ => (grid_origin.1 * WALL_SIZE) + (WALL_SIZE / 2),
-    };
-    let angle: f64 = ANGLE_DIFF_DEGREES * angle_num as f64;
+// Ray length from `at` to the first grid line it crosses along one axis
+fn first_side(at: i32, d: i32, delta: i32) -> i32 {
+    let within = at.rem_euclid(ONE);
+    let to_line = if d < 0 { within } else { ONE - within };
+    (to_line as i64 * delta as i64 / ONE as i64) as i32
+}
 
-    let mut ax = if angle >= 90.0 && angle <= 270.0 {
-        grid_origin.1 * WALL_SIZE - 1
+// |1 / d| in fixed point, or as good as infinite for 0
+fn delta(d: i32) -> i32 {
+    if d == 0 {
+        i32::MAX
     } else {
-        grid_origin.1 * WALL_SIZE + WALL_SIZE
-    };
+        ONE * ONE / d.abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Arena;
 
-    if angle == 90.0 || angle == 270.0 {
-        return None; // divergent tan
+    // Middle of cell (x, y), scaled by ONE
+    fn centre(x: i32, y: i32) -> (i32, i32) {
+        (x * ONE + ONE / 2, y * ONE + ONE / 2)
     }
 
-    let ay_neg = (py as f64
-        + ((px as i32 - ax as i32) as f64 * f64::tan(angle / 180.0 * std::f64::consts::PI)))
-    .floor();
-    if ay_neg < 0.0 {
-        return None;
+    fn wall(game: &mut Game, x: usize, y: usize) {
+        game.board[y * ARENA_SIZE + x] = Some(0);
     }
 
-    let mut ay = ay_neg as usize;
-    let ya = (WALL_SIZE as f64 * f64::tan(angle / 180.0 * std::f64::consts::PI)).floor() as i32;
+    // First occupied cell along the ray, and how far in the ray enters it
+    fn hit(game: &Game, eye: (i32, i32), angle: i32) -> Option<((i32, i32), i32)> {
+        let mut walk = Walk::new(eye, angle);
+        walk.next(game).map(|i| (walk.grid, i.distance))
+    }
 
-    while ax >= 0 && ax < 160 && ay >= 0 && ay < 160 {
-        let gridx = ax / WALL_SIZE;
-        let gridy = ay / WALL_SIZE;
+    #[test]
+    fn axis_aligned_rays() {
+        let mut game = Game::new();
+        for (x, y) in [(14, 10), (10, 7), (5, 10), (10, 12)] {
+            wall(&mut game, x, y);
+        }
+        let eye = centre(10, 10);
+        let half = ONE / 2;
+        assert_eq!(hit(&game, eye, 0), Some(((14, 10), 3 * ONE + half)));
+        assert_eq!(hit(&game, eye, ANGLES / 4), Some(((10, 7), 2 * ONE + half)));
+        assert_eq!(hit(&game, eye, ANGLES / 2), Some(((5, 10), 4 * ONE + half)));
+        assert_eq!(hit(&game, eye, -ANGLES / 4), Some(((10, 12), ONE + half)));
+    }
 
-        unsafe {
-            if let Some(c) = BOARD[40 * gridy + gridx] {
-                let dist = calculate_distance(px, py, ax, ay, angle_num);
-                return Some(Intersection {
-                    kind: IntersectionKind::VerticalGrid,
-                    distance: dist,
-                    colour: c,
-                });
+    #[test]
+    fn rays_along_grid_lines() {
+        let mut game = Game::new();
+        wall(&mut game, 13, 10);
+        wall(&mut game, 10, 6);
+        let corner = (10 * ONE, 10 * ONE);
+        assert_eq!(hit(&game, corner, 0), Some(((13, 10), 3 * ONE)));
+        assert_eq!(hit(&game, corner, ANGLES / 4), Some(((10, 6), 3 * ONE)));
+        // Nothing that way, so it runs off the board
+        assert_eq!(hit(&game, corner, ANGLES / 2), None);
+    }
+
+    #[test]
+    fn eye_on_a_grid_line() {
+        let mut game = Game::new();
+        wall(&mut game, 2, 10);
+        wall(&mut game, 8, 10);
+        wall(&mut game, 5, 7);
+        let eye = (5 * ONE, centre(0, 10).1);
+        assert_eq!(hit(&game, eye, ANGLES / 2), Some(((2, 10), 2 * ONE)));
+        assert_eq!(hit(&game, eye, 0), Some(((8, 10), 3 * ONE)));
+        // Straight up the line between columns 4 and 5
+        assert_eq!(
+            hit(&game, eye, ANGLES / 4),
+            Some(((5, 7), 2 * ONE + ONE / 2))
+        );
+    }
+
+    #[test]
+    fn eye_in_an_edge_cell() {
+        let mut game = Game::new();
+        game.reset(Arena::Open);
+        let last = game.size as i32 - 1;
+        // Inside the west wall, looking out and then across the arena
+        let eye = centre(0, 19);
+        assert_eq!(hit(&game, eye, ANGLES / 2), None);
+        assert_eq!(hit(&game, eye, 0), Some(((last, 19), last * ONE - ONE / 2)));
+        // In the corner, looking along the wall
+        let corner = centre(0, 0);
+        assert_eq!(hit(&game, corner, ANGLES / 4), None);
+        assert_eq!(hit(&game, corner, -ANGLES / 4), Some(((0, 1), ONE / 2)));
+        assert_eq!(hit(&game, corner, 0), Some(((1, 0), ONE / 2)));
+    }
+
+    #[test]
+    fn distance_to_a_long_wall_at_every_angle() {
+        // A column at x = 40 seen facing east, and a row at y = 5 seen
+        // facing north
+        let (mut column, mut row) = (Game::new(), Game::new());
+        for i in 0..ARENA_SIZE {
+            wall(&mut column, 40, i);
+            wall(&mut row, i, 5);
+        }
+        let eye = (27 * ONE + 5000, 27 * ONE + 11000);
+        for angle in -degrees(40)..=degrees(40) {
+            let slant = (angle as f64 * core::f64::consts::TAU / ANGLES as f64).cos();
+            let cases = [
+                (&column, angle, (40 * ONE - eye.0) as f64 / slant),
+                (&row, angle + ANGLES / 4, (eye.1 - 6 * ONE) as f64 / slant),
+            ];
+            for (game, angle, expected) in cases {
+                let (cell, distance) = hit(game, eye, angle).expect("hits the wall");
+                assert!(cell.0 == 40 || cell.1 == 5, "angle {angle} hit {cell:?}");
+                let error = (distance as f64 - expected).abs();
+                assert!(error < (ONE / 64) as f64, "angle {angle} is {error} out");
             }
         }
-
-        let xdiff: i32 = if angle >= 90.0 && angle <= 270.0 {
-            -(WALL_SIZE as i32)
-        } else {
-            WALL_SIZE as i32
-        }; // Ya
-        ax = (ax as i32 + xdiff) as usize;
-        ay = (ay as i32 + ya) as usize;
     }
-    None
 }
@@ -0,0 +1,32 @@
+// xorshift32 - tiny, deterministic and plenty for AI jitter
+pub struct Rng(u32);
+
+impl Rng {
+    pub const fn new(seed: u32) -> Self {
+        // xorshift gets stuck on zero
+        if seed == 0 {
+            Rng(0x9e37_79b9)
+        } else {
+            Rng(seed)
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform-ish value in 0..n
+    pub fn below(&mut self, n: u32) -> u32 {
+        self.next_u32() % n
+    }
+
+    /// True with the given percentage chance
+    pub fn chance(&mut self, percent: u8) -> bool {
+        self.below(100) < percent as u32
+    }
+}
@@ -0,0 +1,118 @@
+use crate::ai::{Difficulty, Personality};
+use crate::game::Arena;
+use crate::raycast::{FOV_DEFAULT, FOV_MAX, FOV_MIN, FOV_STEP};
+use crate::theme::Theme;
+use crate::wasm4::{diskr, diskw};
+
+// Layout on disk: [MAGIC, difficulty, personality, arena, theme, fov, players]
+// New fields go on the end so older saves still load
+const MAGIC: u8 = 0x77;
+const SETTINGS_LEN: usize = 7;
+
+/// Who the second bike is
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Players {
+    // The AI
+    One,
+    // A second player on gamepad 2, split-screen
+    Two,
+}
+
+impl Players {
+    pub fn name(self) -> &'static str {
+        match self {
+            Players::One => "1 vs AI",
+            Players::Two => "2 split screen",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            Players::One => Players::Two,
+            Players::Two => Players::One,
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            0 => Some(Players::One),
+            1 => Some(Players::Two),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    pub personality: Personality,
+    pub arena: Arena,
+    pub theme: Theme,
+    /// First-person field of view in degrees
+    pub fov: u8,
+    pub players: Players,
+}
+
+impl Settings {
+    pub const fn new() -> Self {
+        Settings {
+            difficulty: Difficulty::Normal,
+            personality: Personality::Standard,
+            arena: Arena::Open,
+            theme: Theme::Default,
+            fov: FOV_DEFAULT,
+            players: Players::One,
+        }
+    }
+
+    /// Reads saved settings, falling back to defaults for anything missing
+    pub fn load() -> Self {
+        let mut buf = [0u8; SETTINGS_LEN];
+        let read = unsafe { diskr(buf.as_mut_ptr(), SETTINGS_LEN as u32) } as usize;
+        let mut settings = Settings::new();
+        if read == 0 || buf[0] != MAGIC {
+            return settings;
+        }
+        let byte = |i: usize| if i < read { Some(buf[i]) } else { None };
+        if let Some(d) = byte(1).and_then(Difficulty::from_byte) {
+            settings.difficulty = d;
+        }
+        if let Some(p) = byte(2).and_then(Personality::from_byte) {
+            settings.personality = p;
+        }
+        if let Some(a) = byte(3).and_then(Arena::from_byte) {
+            settings.arena = a;
+        }
+        if let Some(t) = byte(4).and_then(Theme::from_byte) {
+            settings.theme = t;
+        }
+        if let Some(f) = byte(5)
+            .filter(|f| (FOV_MIN..=FOV_MAX).contains(f) && (f - FOV_MIN).is_multiple_of(FOV_STEP))
+        {
+            settings.fov = f;
+        }
+        if let Some(p) = byte(6).and_then(Players::from_byte) {
+            settings.players = p;
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        let buf: [u8; SETTINGS_LEN] = [
+            MAGIC,
+            self.difficulty.to_byte(),
+            self.personality.to_byte(),
+            self.arena.to_byte(),
+            self.theme.to_byte(),
+            self.fov,
+            self.players.to_byte(),
+        ];
+        unsafe {
+            diskw(buf.as_ptr(), SETTINGS_LEN as u32);
+        }
+    }
+}
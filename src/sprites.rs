@@ -0,0 +1,68 @@
+// Sprite and tile data
+use crate::game::{LINK_EAST, LINK_NORTH, LINK_SOUTH, LINK_WEST};
+
+/// Bikes are one tile square
+pub const BIKE_SIZE: u32 = 4;
+
+/// Bike head pointing north, the other directions are rotated and flipped
+/// at draw time. 2bpp: colour 1 is the bike's own colour, colour 2 the
+/// cockpit.
+#[rustfmt::skip]
+pub const BIKE: [u8; 4] = [
+    0b00_01_01_00,
+    0b01_10_10_01,
+    0b01_01_01_01,
+    0b01_01_01_01,
+];
+
+/// Heading arrow shown ahead of each bike during the countdown, pointing
+/// north like BIKE
+#[rustfmt::skip]
+pub const ARROW: [u8; 4] = [
+    0b00_01_01_00,
+    0b01_01_01_01,
+    0b00_01_01_00,
+    0b00_01_01_00,
+];
+
+/// Bike as seen in the first-person view, 8x4 and one bit per pixel with
+/// the leftmost pixel in bit 7. Drawn the same from every side.
+#[rustfmt::skip]
+pub const BIKE_PROFILE: [u8; 4] = [
+    0b0001_1000,
+    0b0011_1100,
+    0b1111_1111,
+    0b0110_0110,
+];
+pub const BIKE_PROFILE_WIDTH: usize = 8;
+
+/// Trail tiles indexed by a cell's LINK_* bits: a 2px line through the
+/// middle of the tile running out to each linked side. One bit per pixel,
+/// leftmost pixel in bit 3.
+pub const TRAIL_TILES: [[u8; 4]; 16] = trail_tiles();
+
+const fn trail_tiles() -> [[u8; 4]; 16] {
+    let mut tiles = [[0; 4]; 16];
+    let mut links = 0;
+    while links < 16 {
+        let l = links as u8;
+        // Middle 2x2 plus the left/right arms
+        let mut middle = 0b0110;
+        if l & LINK_WEST != 0 {
+            middle |= 0b1000;
+        }
+        if l & LINK_EAST != 0 {
+            middle |= 0b0001;
+        }
+        tiles[links][1] = middle;
+        tiles[links][2] = middle;
+        if l & LINK_NORTH != 0 {
+            tiles[links][0] = 0b0110;
+        }
+        if l & LINK_SOUTH != 0 {
+            tiles[links][3] = 0b0110;
+        }
+        links += 1;
+    }
+    tiles
+}
@@ -0,0 +1,85 @@
+// Palette themes. Every theme keeps the same roles - 0 walls and text,
+// 1 and 2 the bikes, 3 the floor - and gives the two bikes colours that
+// differ in brightness as well as hue, so they can't be confused.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Default,
+    Classic,
+    GameBoy,
+    HighContrast,
+    Deuteranopia,
+    Protanopia,
+}
+
+impl Theme {
+    pub fn name(self) -> &'static str {
+        use Theme::*;
+        match self {
+            Default => "Default",
+            Classic => "Classic",
+            GameBoy => "Game Boy",
+            HighContrast => "High contrast",
+            Deuteranopia => "Deuteranopia",
+            Protanopia => "Protanopia",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        use Theme::*;
+        match self {
+            Default => Classic,
+            Classic => GameBoy,
+            GameBoy => HighContrast,
+            HighContrast => Deuteranopia,
+            Deuteranopia => Protanopia,
+            Protanopia => Default,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        use Theme::*;
+        match self {
+            Default => Protanopia,
+            Classic => Default,
+            GameBoy => Classic,
+            HighContrast => GameBoy,
+            Deuteranopia => HighContrast,
+            Protanopia => Deuteranopia,
+        }
+    }
+
+    pub fn palette(self) -> [u32; 4] {
+        use Theme::*;
+        match self {
+            Default => [0x686c73, 0x1e88e5, 0xffc107, 0x000000],
+            // Cyan against orange on near-black
+            Classic => [0x4a5a6a, 0x00e5ff, 0xff8c00, 0x050a14],
+            // The greens for bikes and floor, and a paler shade for the walls
+            // so they stand apart from P1 and text stays readable
+            GameBoy => [0xe0f8d0, 0x8bac0f, 0x306230, 0x0f380f],
+            HighContrast => [0xffffff, 0xffff00, 0xff00ff, 0x000000],
+            // Blue and orange from the Okabe-Ito set, no red/green pairs
+            Deuteranopia => [0xbbbbbb, 0x0072b2, 0xe69f00, 0x000000],
+            // Sky blue and yellow, nothing that relies on seeing red
+            Protanopia => [0x999999, 0x56b4e9, 0xf0e442, 0x000000],
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(b: u8) -> Option<Self> {
+        use Theme::*;
+        match b {
+            0 => Some(Default),
+            1 => Some(Classic),
+            2 => Some(GameBoy),
+            3 => Some(HighContrast),
+            4 => Some(Deuteranopia),
+            5 => Some(Protanopia),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Short effects between game states: palette fades, a wipe and a flash.
+// The screens keep drawing underneath, a transition is applied on top each
+// frame until it runs out.
+use crate::set_palette;
+use crate::wasm4::*;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    // Palette from normal to black
+    FadeOut,
+    // Palette from black to normal
+    FadeIn,
+    // Black curtain drawn back left to right over the new screen
+    Wipe,
+    // Palette jumps to white and settles back
+    Flash,
+}
+
+pub struct Transition {
+    effect: Option<Effect>,
+    frame: u32,
+    frames: u32,
+}
+
+impl Transition {
+    pub const fn new() -> Self {
+        Transition {
+            effect: None,
+            frame: 0,
+            frames: 0,
+        }
+    }
+
+    pub fn start(&mut self, effect: Effect, frames: u32) {
+        self.effect = Some(effect);
+        self.frame = 0;
+        self.frames = frames;
+    }
+
+    pub fn active(&self) -> bool {
+        self.effect.is_some()
+    }
+
+    /// Applies this frame of the effect over `palette`, the palette the game
+    /// would otherwise show. Returns true on the frame it finishes.
+    pub fn update(&mut self, palette: [u32; 4]) -> bool {
+        let Some(effect) = self.effect else {
+            return false;
+        };
+        self.frame += 1;
+        // How far through, 0 to 256
+        let t = self.frame * 256 / self.frames;
+        match effect {
+            Effect::FadeOut => set_palette(palette.map(|c| blend(c, 0x000000, t))),
+            Effect::FadeIn => set_palette(palette.map(|c| blend(0x000000, c, t))),
+            Effect::Flash => set_palette(palette.map(|c| blend(0xffffff, c, t))),
+            Effect::Wipe => {
+                let x = (SCREEN_SIZE * t / 256) as i32;
+                unsafe {
+                    *DRAW_COLORS = 0x4;
+                }
+                rect(x, 0, SCREEN_SIZE, SCREEN_SIZE);
+            }
+        }
+        if self.frame < self.frames {
+            return false;
+        }
+        self.effect = None;
+        // A fade out holds on black until something else takes over
+        if effect != Effect::FadeOut {
+            set_palette(palette);
+        }
+        true
+    }
+}
+
+// Mixes two 0xRRGGBB colours, t out of 256 of the way from a to b
+fn blend(a: u32, b: u32, t: u32) -> u32 {
+    let mut out = 0;
+    for shift in [0, 8, 16] {
+        let (ca, cb) = ((a >> shift) & 0xff, (b >> shift) & 0xff);
+        let c = (ca * (256 - t) + cb * t) / 256;
+        out |= c << shift;
+    }
+    out
+}
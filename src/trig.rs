@@ -0,0 +1,143 @@
+// Fixed-point trig for the raycaster. Angles are in 1/4096ths of a turn,
+// anticlockwise from east, and results are scaled by ONE. The tables are
+// worked out at compile time, so nothing here touches floats at runtime.
+
+/// Angle units in a full turn
+pub const ANGLES: i32 = 4096;
+/// Fixed-point 1.0
+pub const ONE: i32 = 1 << 14;
+
+const QUARTER: usize = ANGLES as usize / 4;
+
+// sin over the first quarter turn, both ends included
+static SIN_TABLE: [u16; QUARTER + 1] = sin_table();
+
+const fn sin_table() -> [u16; QUARTER + 1] {
+    let mut table = [0; QUARTER + 1];
+    let mut i = 0;
+    while i <= QUARTER {
+        let x = i as f64 * core::f64::consts::FRAC_PI_2 / QUARTER as f64;
+        // Taylor series, plenty accurate up to pi/2
+        let (mut term, mut sum, mut n) = (x, x, 1);
+        while n < 10 {
+            term *= -x * x / ((2 * n) * (2 * n + 1)) as f64;
+            sum += term;
+            n += 1;
+        }
+        table[i] = (sum * ONE as f64 + 0.5) as u16;
+        i += 1;
+    }
+    table
+}
+
+/// Whole degrees in angle units, rounded down
+pub const fn degrees(d: i32) -> i32 {
+    d * ANGLES / 360
+}
+
+pub fn sin(angle: i32) -> i32 {
+    let a = angle.rem_euclid(ANGLES) as usize;
+    match a / QUARTER {
+        0 => SIN_TABLE[a] as i32,
+        1 => SIN_TABLE[2 * QUARTER - a] as i32,
+        2 => -(SIN_TABLE[a - 2 * QUARTER] as i32),
+        _ => -(SIN_TABLE[4 * QUARTER - a] as i32),
+    }
+}
+
+pub fn cos(angle: i32) -> i32 {
+    sin(angle + ANGLES / 4)
+}
+
+// atan of k / 256 for k from 0 to 256, in angle units
+static ATAN_TABLE: [u16; 257] = atan_table();
+
+const fn atan_table() -> [u16; 257] {
+    let sines = sin_table();
+    let mut table = [0; 257];
+    let mut k = 0;
+    while k <= 256 {
+        // Smallest angle up to an eighth of a turn whose tan reaches k / 256
+        let (mut lo, mut hi) = (0, QUARTER / 2);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if sines[mid] as u32 * 256 >= k as u32 * sines[QUARTER - mid] as u32 {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        table[k] = lo as u16;
+        k += 1;
+    }
+    table
+}
+
+/// Angle of the vector (x, y), y pointing up, like f64::atan2
+pub fn atan2(y: i32, x: i32) -> i32 {
+    let (ax, ay) = (x.unsigned_abs() as u64, y.unsigned_abs() as u64);
+    if ax == 0 && ay == 0 {
+        return 0;
+    }
+    // Fold into the first eighth of a turn and back out
+    let mut a = if ax >= ay {
+        ATAN_TABLE[(ay * 256 / ax) as usize] as i32
+    } else {
+        ANGLES / 4 - ATAN_TABLE[(ax * 256 / ay) as usize] as i32
+    };
+    if x < 0 {
+        a = ANGLES / 2 - a;
+    }
+    if y < 0 {
+        a = -a;
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::TAU;
+
+    fn radians(angle: i32) -> f64 {
+        angle as f64 * TAU / ANGLES as f64
+    }
+
+    #[test]
+    fn sin_and_cos_match_f64() {
+        for angle in -ANGLES..2 * ANGLES {
+            let s = sin(angle) as f64 / ONE as f64;
+            let c = cos(angle) as f64 / ONE as f64;
+            assert!((s - radians(angle).sin()).abs() < 1e-4, "sin({angle})");
+            assert!((c - radians(angle).cos()).abs() < 1e-4, "cos({angle})");
+        }
+    }
+
+    #[test]
+    fn quarter_turns_are_exact() {
+        assert_eq!([sin(0), cos(0)], [0, ONE]);
+        assert_eq!([sin(ANGLES / 4), cos(ANGLES / 4)], [ONE, 0]);
+        assert_eq!([sin(ANGLES / 2), cos(ANGLES / 2)], [0, -ONE]);
+        assert_eq!([sin(-ANGLES / 4), cos(-ANGLES / 4)], [-ONE, 0]);
+    }
+
+    #[test]
+    fn atan2_matches_f64() {
+        for scale in [1, 37, ONE] {
+            for y in -40..=40 {
+                for x in -40..=40 {
+                    if x == 0 && y == 0 {
+                        continue;
+                    }
+                    let (x, y) = (x * scale, y * scale);
+                    let expected = (y as f64).atan2(x as f64) * ANGLES as f64 / TAU;
+                    // Compare the long way round, so +-half a turn agree
+                    let diff = (atan2(y, x) as f64 - expected).rem_euclid(ANGLES as f64);
+                    let diff = diff.min(ANGLES as f64 - diff);
+                    assert!(diff <= 3.0, "atan2({y}, {x}) is {diff} out");
+                }
+            }
+        }
+        assert_eq!(atan2(0, 0), 0);
+    }
+}
@@ -0,0 +1,57 @@
+// Rectangles of the screen a view draws into: the whole screen normally,
+// or one half each in split-screen.
+use crate::hud::HUD_HEIGHT;
+use crate::wasm4::{FRAMEBUFFER, SCREEN_SIZE};
+
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// The whole screen, HUD strip and all
+    pub const FULL: Viewport = Viewport {
+        x: 0,
+        y: 0,
+        width: SCREEN_SIZE as usize,
+        height: SCREEN_SIZE as usize,
+    };
+
+    /// Full width under the HUD strip, for one player
+    pub const MAIN: Viewport = Viewport {
+        x: 0,
+        y: HUD_HEIGHT,
+        width: SCREEN_SIZE as usize,
+        height: SCREEN_SIZE as usize - HUD_HEIGHT,
+    };
+
+    /// Side by side halves under the HUD strip, one per player
+    pub const SPLIT: [Viewport; 2] = [
+        Viewport {
+            x: 0,
+            y: HUD_HEIGHT,
+            width: SCREEN_SIZE as usize / 2,
+            height: SCREEN_SIZE as usize - HUD_HEIGHT,
+        },
+        Viewport {
+            x: SCREEN_SIZE as usize / 2,
+            y: HUD_HEIGHT,
+            width: SCREEN_SIZE as usize / 2,
+            height: SCREEN_SIZE as usize - HUD_HEIGHT,
+        },
+    ];
+
+    /// Sets pixel (x, y) counted from the viewport's top left
+    pub fn pixel(&self, x: usize, y: usize, colour: u8) {
+        let ix = (self.y + y) * SCREEN_SIZE as usize + self.x + x;
+        // The framebuffer keeps the leftmost pixel in the low bits
+        let shift = (ix % 4) * 2;
+        unsafe {
+            let byte = &mut (*FRAMEBUFFER)[ix / 4];
+            *byte = (*byte & !(3 << shift)) | (colour << shift);
+        }
+    }
+}
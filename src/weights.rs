@@ -0,0 +1,5 @@
+// Generated by `cargo run --release -p tools --bin tune`
+use crate::ai::Weights;
+
+// space, territory, opponent, contact, ahead, sides, contested
+pub const TUNED_WEIGHTS: Weights = [4, 4, -2, 1, 0, 0, -1494];
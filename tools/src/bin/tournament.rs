@@ -0,0 +1,185 @@
+// Headless AI-vs-AI tournament. Plays seeded matches between every pair of
+// AIs on every arena and reports win/draw/loss rates with 95% confidence
+// intervals, so AI changes can be measured rather than eyeballed.
+//
+// cargo run --release -p tools --bin tournament -- --matches 2000 --ai hard --ai insane:aggressor
+use cart::ai::{AiParams, Difficulty, Personality};
+use cart::game::{Arena, Outcome};
+use std::process::exit;
+use tools::{match_setup, play_match};
+
+const USAGE: &str = "\
+Usage: tournament [options]
+
+Options:
+  --matches N    matches per pairing (default 1000)
+  --seed S       base seed (default 1)
+  --ai NAME      AI to enter as difficulty[:personality], repeatable
+                 (default: every difficulty with the standard personality)
+  --arena NAME   arena to play on, repeatable (default: all)";
+
+// z for a two-sided 95% interval
+const Z: f64 = 1.96;
+
+#[derive(Clone, Copy)]
+struct Entrant {
+    difficulty: Difficulty,
+    personality: Personality,
+}
+
+impl Entrant {
+    fn parse(name: &str) -> Result<Self, String> {
+        let (difficulty, personality) = name.split_once(':').unwrap_or((name, "standard"));
+        Ok(Entrant {
+            difficulty: Difficulty::ALL
+                .into_iter()
+                .find(|d| d.name().eq_ignore_ascii_case(difficulty))
+                .ok_or(format!("unknown difficulty {}", difficulty))?,
+            personality: Personality::ALL
+                .into_iter()
+                .find(|p| p.name().eq_ignore_ascii_case(personality))
+                .ok_or(format!("unknown personality {}", personality))?,
+        })
+    }
+
+    fn name(&self) -> String {
+        match self.personality {
+            Personality::Standard => self.difficulty.name().to_string(),
+            p => format!("{} {}", self.difficulty.name(), p.name()),
+        }
+    }
+
+    fn params(&self) -> AiParams {
+        self.difficulty.params().with_personality(self.personality)
+    }
+}
+
+struct Options {
+    matches: u32,
+    seed: u32,
+    ais: Vec<Entrant>,
+    arenas: Vec<Arena>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        matches: 1000,
+        seed: 1,
+        ais: Vec::new(),
+        arenas: Vec::new(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--matches" => {
+                options.matches = value()?.parse().map_err(|e| format!("--matches: {}", e))?
+            }
+            "--seed" => options.seed = value()?.parse().map_err(|e| format!("--seed: {}", e))?,
+            "--ai" => options.ais.push(Entrant::parse(&value()?)?),
+            "--arena" => {
+                let name = value()?;
+                let arena = Arena::ALL
+                    .into_iter()
+                    .find(|a| a.name().eq_ignore_ascii_case(&name))
+                    .ok_or(format!("unknown arena {}", name))?;
+                options.arenas.push(arena);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    if options.ais.is_empty() {
+        options.ais = Difficulty::ALL
+            .into_iter()
+            .map(|difficulty| Entrant {
+                difficulty,
+                personality: Personality::Standard,
+            })
+            .collect();
+    }
+    if options.arenas.is_empty() {
+        options.arenas = Arena::ALL.to_vec();
+    }
+    Ok(options)
+}
+
+/// Wilson score interval for k successes out of n
+fn wilson(k: u32, n: u32) -> (f64, f64) {
+    let (k, n) = (k as f64, n as f64);
+    let p = k / n;
+    let denom = 1.0 + Z * Z / n;
+    let centre = (p + Z * Z / (2.0 * n)) / denom;
+    let half = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denom;
+    (centre - half, centre + half)
+}
+
+fn print_rate(label: &str, k: u32, n: u32) {
+    let (lo, hi) = wilson(k, n);
+    println!(
+        "  {:<7}{:>6.1}%  [{:.1}%, {:.1}%]",
+        label,
+        100.0 * k as f64 / n as f64,
+        100.0 * lo,
+        100.0 * hi
+    );
+}
+
+fn run_pairing(options: &Options, a: Entrant, b: Entrant) {
+    let (mut wins, mut draws, mut losses) = (0, 0, 0);
+    let mut lengths = Vec::with_capacity(options.matches as usize);
+
+    for i in 0..options.matches {
+        let (arena, seed, swap) = match_setup(&options.arenas, options.seed, i);
+        let (outcome, ticks) = play_match(arena, a.params(), b.params(), seed, swap);
+        match outcome {
+            Outcome::Winner(1) => wins += 1,
+            Outcome::Winner(_) => losses += 1,
+            Outcome::Draw => draws += 1,
+        }
+        lengths.push(ticks as f64);
+    }
+
+    let n = options.matches;
+    let mean = lengths.iter().sum::<f64>() / n as f64;
+    let variance = lengths.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / (n as f64 - 1.0);
+    println!("{} vs {}", a.name(), b.name());
+    print_rate("wins", wins, n);
+    print_rate("draws", draws, n);
+    print_rate("losses", losses, n);
+    println!(
+        "  length {:.1} ticks ± {:.1}",
+        mean,
+        Z * (variance / n as f64).sqrt()
+    );
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            exit(2);
+        }
+    };
+    if options.matches < 2 {
+        eprintln!("--matches must be at least 2");
+        exit(2);
+    }
+
+    let arenas: Vec<_> = options.arenas.iter().map(|a| a.name()).collect();
+    println!(
+        "{} matches per pairing on {}, seed {}\n",
+        options.matches,
+        arenas.join(", "),
+        options.seed
+    );
+    for (i, &a) in options.ais.iter().enumerate() {
+        for &b in &options.ais[i + 1..] {
+            run_pairing(&options, a, b);
+        }
+    }
+}
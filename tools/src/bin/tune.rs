@@ -0,0 +1,161 @@
+// Self-play weight tuner for the Tuned personality. Hill climbs from the
+// weights currently baked into the cart: each generation mutates the best
+// weights so far and keeps the mutant only if it beats them in a series of
+// matches. The winner is emitted as a Rust const table for src/weights.rs.
+//
+// cargo run --release -p tools --bin tune -- --generations 200 --out src/weights.rs
+use cart::ai::{AiParams, Difficulty, Strategy, Weights, FEATURES, FEATURE_NAMES};
+use cart::game::{Arena, Outcome};
+use cart::rng::Rng;
+use cart::weights::TUNED_WEIGHTS;
+use std::process::exit;
+use tools::{match_setup, play_match};
+
+const USAGE: &str = "\
+Usage: tune [options]
+
+Options:
+  --generations N  mutations to try (default 100)
+  --matches N      matches per comparison (default 100)
+  --seed S         base seed (default 1)
+  --out PATH       write the weights table here instead of stdout";
+
+// How many standard errors better than the incumbent a mutant must do
+const ACCEPT_Z: f64 = 1.0;
+
+struct Options {
+    generations: u32,
+    matches: u32,
+    seed: u32,
+    out: Option<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        generations: 100,
+        matches: 100,
+        seed: 1,
+        out: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--generations" => {
+                options.generations = value()?
+                    .parse()
+                    .map_err(|e| format!("--generations: {}", e))?
+            }
+            "--matches" => {
+                options.matches = value()?.parse().map_err(|e| format!("--matches: {}", e))?
+            }
+            "--seed" => options.seed = value()?.parse().map_err(|e| format!("--seed: {}", e))?,
+            "--out" => options.out = Some(value()?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+/// Insane's knobs, but driven by the given weights
+fn params(weights: Weights) -> AiParams {
+    let mut params = Difficulty::Insane.params();
+    params.strategy = Strategy::Eval { weights };
+    params
+}
+
+/// Wins, draws and losses for `a` against `b`
+fn series(a: AiParams, b: AiParams, matches: u32, seed: u32) -> (u32, u32, u32) {
+    let (mut wins, mut draws, mut losses) = (0, 0, 0);
+    for i in 0..matches {
+        let (arena, seed, swap) = match_setup(&Arena::ALL, seed, i);
+        match play_match(arena, a, b, seed, swap).0 {
+            Outcome::Winner(1) => wins += 1,
+            Outcome::Winner(_) => losses += 1,
+            Outcome::Draw => draws += 1,
+        }
+    }
+    (wins, draws, losses)
+}
+
+fn mutate(weights: &Weights, rng: &mut Rng) -> Weights {
+    let mut mutant = *weights;
+    // Usually nudge one weight, sometimes two
+    for _ in 0..1 + rng.below(2) {
+        let i = rng.below(FEATURES as u32) as usize;
+        let span = mutant[i].abs() / 2 + 2;
+        let delta = rng.below(2 * span as u32 + 1) as i32 - span;
+        mutant[i] += if delta == 0 { 1 } else { delta };
+    }
+    mutant
+}
+
+fn table(weights: &Weights) -> String {
+    let values: Vec<_> = weights.iter().map(|w| w.to_string()).collect();
+    format!(
+        "// Generated by `cargo run --release -p tools --bin tune`\n\
+         use crate::ai::Weights;\n\n\
+         // {}\n\
+         pub const TUNED_WEIGHTS: Weights = [{}];\n",
+        FEATURE_NAMES.join(", "),
+        values.join(", ")
+    )
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            exit(2);
+        }
+    };
+
+    let mut rng = Rng::new(options.seed);
+    let mut best = TUNED_WEIGHTS;
+    eprintln!("starting from {:?}", best);
+    for generation in 0..options.generations {
+        let mutant = mutate(&best, &mut rng);
+        // Fresh seeds every generation so nothing overfits to one set of spawns
+        let seed = rng.next_u32();
+        let (wins, draws, losses) = series(params(mutant), params(best), options.matches, seed);
+        // Sign test on the decisive games
+        let decisive = (wins + losses) as f64;
+        let accepted =
+            decisive > 0.0 && (wins as f64 - losses as f64) >= ACCEPT_Z * decisive.sqrt();
+        eprintln!(
+            "gen {:>4} {:?} {}/{}/{}{}",
+            generation,
+            mutant,
+            wins,
+            draws,
+            losses,
+            if accepted { " accepted" } else { "" }
+        );
+        if accepted {
+            best = mutant;
+        }
+    }
+
+    let insane = Difficulty::Insane.params();
+    let (wins, draws, losses) = series(params(best), insane, options.matches, options.seed);
+    eprintln!(
+        "best {:?} vs Insane: {}/{}/{} (W/D/L)",
+        best, wins, draws, losses
+    );
+
+    let table = table(&best);
+    match options.out {
+        Some(path) => {
+            if let Err(e) = std::fs::write(&path, table) {
+                eprintln!("writing {}: {}", path, e);
+                exit(1);
+            }
+        }
+        None => print!("{}", table),
+    }
+}
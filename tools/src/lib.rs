@@ -0,0 +1,41 @@
+// Shared between the host-side tools
+use cart::ai::{Ai, AiParams};
+use cart::game::{Arena, Game, Outcome};
+use cart::rng::Rng;
+
+/// Plays one match, returning the outcome from `a`'s point of view
+/// (`Winner(1)` means `a` won) and its length in ticks
+pub fn play_match(arena: Arena, a: AiParams, b: AiParams, seed: u32, swap: bool) -> (Outcome, u32) {
+    let mut rng = Rng::new(seed);
+    let mut game = Game::new();
+    game.reset(arena);
+    game.randomise_spawns(&mut rng);
+    let mut ais = [Ai::new(a, rng.next_u32()), Ai::new(b, rng.next_u32())];
+    // Entrant `slot` drives player `slot ^ swap`
+    let player = |slot: usize| slot ^ swap as usize;
+
+    while game.outcome.is_none() {
+        for (slot, ai) in ais.iter_mut().enumerate() {
+            let dir = ai.think(&game, player(slot));
+            game.players[player(slot)].direction = dir;
+        }
+        game.step();
+    }
+
+    let outcome = match game.outcome {
+        Some(Outcome::Winner(index)) if index as usize - 1 == player(0) => Outcome::Winner(1),
+        Some(Outcome::Winner(_)) => Outcome::Winner(2),
+        _ => Outcome::Draw,
+    };
+    (outcome, game.ticks)
+}
+
+/// Seed and side for the `i`th match of a series. Each seed and arena is
+/// played twice with the sides swapped, so neither AI gets a systematically
+/// better spawn.
+pub fn match_setup(arenas: &[Arena], base_seed: u32, i: u32) -> (Arena, u32, bool) {
+    let round = i / 2;
+    let arena = arenas[round as usize % arenas.len()];
+    let seed = base_seed.wrapping_add(round.wrapping_mul(0x9e37_79b9));
+    (arena, seed, i % 2 == 1)
+}